@@ -0,0 +1,112 @@
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use ryogoku_operator::{DevnetClient, MintUnit};
+use serde_json::{json, Value};
+
+/// Token to mint.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Unit {
+    /// Ether, in wei.
+    Wei,
+    /// Stark, in fri.
+    Fri,
+}
+
+impl From<Unit> for MintUnit {
+    fn from(unit: Unit) -> Self {
+        match unit {
+            Unit::Wei => MintUnit::Wei,
+            Unit::Fri => MintUnit::Fri,
+        }
+    }
+}
+
+/// Mint tokens to the address and print its new balance.
+pub async fn fund(
+    devnet: &DevnetClient,
+    address: &str,
+    amount: u128,
+    unit: Option<Unit>,
+) -> Result<()> {
+    let response = devnet.mint(address, amount, unit.map(Into::into)).await?;
+    let balance = match &response.new_balance {
+        Value::String(balance) => balance.clone(),
+        balance => balance.to_string(),
+    };
+    let unit = response
+        .unit
+        .unwrap_or_else(|| "wei".to_string())
+        .to_lowercase();
+    println!("{} balance: {} {}", address, balance, unit);
+    Ok(())
+}
+
+/// Create `blocks` blocks, including the pending transactions in the first one.
+pub async fn mine(devnet: &DevnetClient, name: &str, blocks: u32) -> Result<()> {
+    for _ in 0..blocks {
+        let block: Value = devnet.post("gateway", "create_block", &json!({})).await?;
+        let hash = block["block_hash"].as_str().unwrap_or("unknown");
+        println!("devnet {} mined block {}", name, hash);
+    }
+    Ok(())
+}
+
+/// Set the time of the next block, in Unix time seconds.
+pub async fn set_time(devnet: &DevnetClient, name: &str, time: u64) -> Result<()> {
+    let _: Value = devnet
+        .post("gateway", "set_time", &json!({ "time": time }))
+        .await?;
+    println!("devnet {} time set to {}", name, time);
+    Ok(())
+}
+
+/// Move the devnet time forward.
+pub async fn increase_time(devnet: &DevnetClient, name: &str, duration: Duration) -> Result<()> {
+    let _: Value = devnet
+        .post(
+            "gateway",
+            "increase_time",
+            &json!({ "time": duration.as_secs() }),
+        )
+        .await?;
+    println!(
+        "devnet {} time increased by {}",
+        name,
+        humantime::format_duration(duration)
+    );
+    Ok(())
+}
+
+/// Abort the blocks from the given block to the latest one, reverting their transactions.
+pub async fn abort_blocks(devnet: &DevnetClient, name: &str, from: &str) -> Result<()> {
+    let response: Value = devnet
+        .post(
+            "gateway",
+            "abort_blocks",
+            &json!({ "starting_block_hash": from }),
+        )
+        .await?;
+    let aborted = response["aborted"]
+        .as_array()
+        .map(|blocks| blocks.len())
+        .unwrap_or(0);
+    println!("devnet {} aborted {} blocks", name, aborted);
+    Ok(())
+}
+
+/// Reset the devnet to genesis in place, with its `restart` endpoint.
+pub async fn reset(devnet: &DevnetClient, name: &str) -> Result<()> {
+    let _: Value = devnet
+        .post("gateway", "restart", &json!({}))
+        .await
+        .with_context(|| {
+            format!(
+                "failed to reset devnet {}, use `ryogoku devnet restart` if its image doesn't support it",
+                name
+            )
+        })?;
+    println!("devnet {} reset", name);
+    Ok(())
+}
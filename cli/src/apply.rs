@@ -0,0 +1,454 @@
+use std::{
+    collections::BTreeSet,
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use ryogoku_operator::{
+    kube::{
+        api::{Api, ApiResource, DeleteParams, DynamicObject, ListParams, Patch, PatchParams},
+        Client, ResourceExt,
+    },
+    ClusterDevnetTemplate, Devnet, DevnetClaim, DevnetPool, DevnetTemplate,
+};
+use serde::Deserialize;
+use similar::TextDiff;
+
+/// Field manager of the resources applied by the cli.
+static FIELD_MANAGER: &str = "ryogoku-cli";
+
+/// Options of the `apply` command.
+pub struct ApplyOptions {
+    pub prune: bool,
+    pub selector: Option<String>,
+    pub diff: bool,
+    pub dry_run: bool,
+    pub force_conflicts: bool,
+}
+
+/// Ryogoku resource kind.
+struct Kind {
+    resource: ApiResource,
+    namespaced: bool,
+}
+
+impl Kind {
+    /// Returns the Ryogoku kinds, templates first so devnets and pools can use them.
+    fn all() -> Vec<Kind> {
+        vec![
+            Kind {
+                resource: ApiResource::erase::<ClusterDevnetTemplate>(&()),
+                namespaced: false,
+            },
+            Kind {
+                resource: ApiResource::erase::<DevnetTemplate>(&()),
+                namespaced: true,
+            },
+            Kind {
+                resource: ApiResource::erase::<DevnetPool>(&()),
+                namespaced: true,
+            },
+            Kind {
+                resource: ApiResource::erase::<Devnet>(&()),
+                namespaced: true,
+            },
+            Kind {
+                resource: ApiResource::erase::<DevnetClaim>(&()),
+                namespaced: true,
+            },
+        ]
+    }
+
+    fn api(&self, client: Client, namespace: Option<&str>) -> Api<DynamicObject> {
+        match namespace {
+            Some(namespace) if self.namespaced => {
+                Api::namespaced_with(client, namespace, &self.resource)
+            }
+            _ => Api::all_with(client, &self.resource),
+        }
+    }
+
+    /// Returns the name of the object, like `devnet.ryogoku.stark/my-devnet`.
+    fn display(&self, name: &str) -> String {
+        format!(
+            "{}.{}/{}",
+            self.resource.kind.to_lowercase(),
+            self.resource.group,
+            name
+        )
+    }
+}
+
+/// Ryogoku resource read from the manifests.
+pub struct Manifest {
+    kind: usize,
+    object: DynamicObject,
+}
+
+impl Manifest {
+    fn namespace(&self, kinds: &[Kind], default: &str) -> Option<String> {
+        kinds[self.kind].namespaced.then(|| {
+            self.object
+                .namespace()
+                .unwrap_or_else(|| default.to_string())
+        })
+    }
+}
+
+/// Read the Ryogoku resources in the files, sorted in apply order.
+///
+/// Directories are read one level deep, and `-` reads stdin. Only `.yaml`, `.yml` and
+/// `.json` files are read from directories.
+pub fn read_manifests(paths: &[PathBuf]) -> Result<Vec<Manifest>> {
+    let kinds = Kind::all();
+    let mut files = Vec::default();
+    for path in paths {
+        if path.as_os_str() == "-" {
+            let mut content = String::default();
+            std::io::stdin().read_to_string(&mut content)?;
+            files.push(("stdin".to_string(), content));
+        } else if path.is_dir() {
+            let mut entries = fs::read_dir(path)?
+                .map(|entry| Ok(entry?.path()))
+                .collect::<Result<Vec<_>>>()?;
+            entries.sort();
+            for entry in entries {
+                let extension = entry.extension().and_then(|e| e.to_str());
+                if matches!(extension, Some("yaml" | "yml" | "json")) {
+                    files.push(read_file(&entry)?);
+                }
+            }
+        } else {
+            files.push(read_file(path)?);
+        }
+    }
+
+    let mut manifests = Vec::default();
+    for (file, content) in files {
+        for document in serde_yaml::Deserializer::from_str(&content) {
+            let value = serde_yaml::Value::deserialize(document)
+                .with_context(|| format!("invalid manifest in {}", file))?;
+            if value.is_null() {
+                continue;
+            }
+
+            let object: DynamicObject = serde_yaml::from_value(value)
+                .with_context(|| format!("invalid manifest in {}", file))?;
+            let types = object
+                .types
+                .as_ref()
+                .ok_or_else(|| anyhow!("manifest without apiVersion and kind in {}", file))?;
+            let kind = kinds
+                .iter()
+                .position(|kind| {
+                    kind.resource.api_version == types.api_version
+                        && kind.resource.kind == types.kind
+                })
+                .ok_or_else(|| {
+                    anyhow!(
+                        "unsupported resource {} {} in {}, only Ryogoku resources can be applied",
+                        types.api_version,
+                        types.kind,
+                        file
+                    )
+                })?;
+            if object.metadata.name.is_none() {
+                bail!("{} without name in {}", types.kind, file);
+            }
+
+            manifests.push(Manifest { kind, object });
+        }
+    }
+
+    manifests.sort_by_key(|manifest| manifest.kind);
+    Ok(manifests)
+}
+
+fn read_file(path: &Path) -> Result<(String, String)> {
+    let content =
+        fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    Ok((path.display().to_string(), content))
+}
+
+/// Server-side apply the Ryogoku resources in the files.
+///
+/// With `prune`, the resources matching the selector that are not in the files are deleted.
+/// Resources owned by pools and claims are never pruned.
+pub async fn apply(
+    client: Client,
+    namespace: &str,
+    manifests: &[Manifest],
+    opts: ApplyOptions,
+) -> Result<()> {
+    let kinds = Kind::all();
+    let dry_run = opts.dry_run || opts.diff;
+    let suffix = if opts.dry_run { " (dry run)" } else { "" };
+
+    let mut pp = PatchParams::apply(FIELD_MANAGER);
+    pp.force = opts.force_conflicts;
+    pp.dry_run = dry_run;
+
+    let mut applied = BTreeSet::default();
+    let mut namespaces = BTreeSet::from([namespace.to_string()]);
+    for manifest in manifests {
+        let kind = &kinds[manifest.kind];
+        let object_namespace = manifest.namespace(&kinds, namespace);
+        let name = manifest.object.name_any();
+        let api = kind.api(client.clone(), object_namespace.as_deref());
+
+        let mut object = manifest.object.clone();
+        object.metadata.namespace = object_namespace.clone();
+
+        let existing = api.get_opt(&name).await?;
+        let result = api
+            .patch(&name, &pp, &Patch::Apply(&object))
+            .await
+            .with_context(|| format!("failed to apply {}", kind.display(&name)))?;
+
+        if opts.diff {
+            if let Some(diff) = diff(existing.as_ref(), &result, &kind.display(&name))? {
+                print!("{}", diff);
+            }
+        } else {
+            let outcome = match &existing {
+                None => "created",
+                Some(existing) if existing.resource_version() == result.resource_version() => {
+                    "unchanged"
+                }
+                Some(_) => "configured",
+            };
+            println!("{} {}{}", kind.display(&name), outcome, suffix);
+        }
+
+        applied.insert((manifest.kind, object_namespace.clone(), name));
+        namespaces.extend(object_namespace);
+    }
+
+    if !opts.prune {
+        return Ok(());
+    }
+
+    let selector = opts
+        .selector
+        .as_deref()
+        .ok_or_else(|| anyhow!("--prune requires a label selector"))?;
+    let lp = ListParams::default().labels(selector);
+    let dp = DeleteParams {
+        dry_run,
+        ..DeleteParams::default()
+    };
+    let suffix = if dry_run { " (dry run)" } else { "" };
+
+    for (index, kind) in kinds.iter().enumerate().rev() {
+        let scopes: Vec<_> = if kind.namespaced {
+            namespaces.iter().map(|ns| Some(ns.as_str())).collect()
+        } else {
+            vec![None]
+        };
+
+        for scope in scopes {
+            let api = kind.api(client.clone(), scope);
+            for object in api.list(&lp).await? {
+                let name = object.name_any();
+                let key = (index, object.namespace(), name.clone());
+                // objects created by other tools or by the operator are never pruned.
+                if applied.contains(&key)
+                    || !object.owner_references().is_empty()
+                    || !applied_by_cli(&object)
+                {
+                    continue;
+                }
+
+                api.delete(&name, &dp).await?;
+                println!("{} pruned{}", kind.display(&name), suffix);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Delete the Ryogoku resources in the files, in the reverse apply order.
+pub async fn delete(
+    client: Client,
+    namespace: &str,
+    manifests: &[Manifest],
+    dry_run: bool,
+) -> Result<()> {
+    let kinds = Kind::all();
+    let dp = DeleteParams {
+        dry_run,
+        ..DeleteParams::default()
+    };
+    let suffix = if dry_run { " (dry run)" } else { "" };
+
+    for manifest in manifests.iter().rev() {
+        let kind = &kinds[manifest.kind];
+        let object_namespace = manifest.namespace(&kinds, namespace);
+        let name = manifest.object.name_any();
+        let api = kind.api(client.clone(), object_namespace.as_deref());
+
+        match api.delete(&name, &dp).await {
+            Ok(_) => println!("{} deleted{}", kind.display(&name), suffix),
+            Err(ryogoku_operator::kube::Error::Api(err)) if err.code == 404 => {
+                println!("{} not found", kind.display(&name))
+            }
+            Err(err) => {
+                return Err(err)
+                    .with_context(|| format!("failed to delete {}", kind.display(&name)))
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns true if the object has fields managed by the cli.
+fn applied_by_cli(object: &DynamicObject) -> bool {
+    object
+        .metadata
+        .managed_fields
+        .iter()
+        .flatten()
+        .any(|fields| fields.manager.as_deref() == Some(FIELD_MANAGER))
+}
+
+/// Returns the diff between the live object and the applied one, or `None` if they are
+/// the same.
+fn diff(
+    existing: Option<&DynamicObject>,
+    applied: &DynamicObject,
+    name: &str,
+) -> Result<Option<String>> {
+    let existing = existing.map(normalized).transpose()?.unwrap_or_default();
+    let applied = normalized(applied)?;
+
+    if existing == applied {
+        return Ok(None);
+    }
+
+    let diff = TextDiff::from_lines(&existing, &applied)
+        .unified_diff()
+        .header(&format!("live/{}", name), &format!("applied/{}", name))
+        .to_string();
+    Ok(Some(diff))
+}
+
+/// Serialize the object, without the fields managed by the api server and the status.
+fn normalized(object: &DynamicObject) -> Result<String> {
+    let mut object = object.clone();
+    object.metadata.managed_fields = None;
+    object.metadata.resource_version = None;
+    object.metadata.generation = None;
+    object.metadata.uid = None;
+    object.metadata.creation_timestamp = None;
+    if let Some(data) = object.data.as_object_mut() {
+        data.remove("status");
+    }
+    Ok(serde_yaml::to_string(&object)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use ryogoku_operator::k8s_openapi::apimachinery::pkg::apis::meta::v1::ManagedFieldsEntry;
+    use serde_json::json;
+
+    use super::*;
+
+    fn object(value: serde_json::Value) -> DynamicObject {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn read_manifests_sorts_templates_first() {
+        let dir = std::env::temp_dir().join(format!("ryogoku-apply-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("a-devnets.yaml"),
+            "apiVersion: ryogoku.stark/v1\nkind: Devnet\nmetadata:\n  name: first\n---\napiVersion: ryogoku.stark/v1\nkind: DevnetClaim\nmetadata:\n  name: claim\nspec:\n  pool: ci\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("b-templates.yml"),
+            "apiVersion: ryogoku.stark/v1\nkind: DevnetTemplate\nmetadata:\n  name: template\nspec: {}\n---\napiVersion: ryogoku.stark/v1\nkind: Devnet\nmetadata:\n  name: second\n",
+        )
+        .unwrap();
+        fs::write(dir.join("notes.txt"), "not a manifest").unwrap();
+
+        let manifests = read_manifests(std::slice::from_ref(&dir));
+        fs::remove_dir_all(&dir).unwrap();
+
+        let names: Vec<_> = manifests
+            .unwrap()
+            .iter()
+            .map(|manifest| manifest.object.name_any())
+            .collect();
+        assert_eq!(names, ["template", "first", "second", "claim"]);
+    }
+
+    #[test]
+    fn diff_ignores_server_fields() {
+        let applied = object(json!({
+            "apiVersion": "ryogoku.stark/v1",
+            "kind": "Devnet",
+            "metadata": { "name": "my-devnet", "resourceVersion": "2", "uid": "abc" },
+            "spec": { "seed": "42" },
+            "status": { "state": "Running" },
+        }));
+        let existing = object(json!({
+            "apiVersion": "ryogoku.stark/v1",
+            "kind": "Devnet",
+            "metadata": { "name": "my-devnet", "resourceVersion": "1", "generation": 1 },
+            "spec": { "seed": "42" },
+        }));
+        assert_eq!(
+            normalized(&existing).unwrap(),
+            normalized(&applied).unwrap()
+        );
+        assert!(!normalized(&applied).unwrap().contains("status"));
+        assert_eq!(diff(Some(&existing), &applied, "my-devnet").unwrap(), None);
+
+        let changed = object(json!({
+            "apiVersion": "ryogoku.stark/v1",
+            "kind": "Devnet",
+            "metadata": { "name": "my-devnet" },
+            "spec": { "seed": "43" },
+        }));
+        let diff = diff(Some(&existing), &changed, "my-devnet")
+            .unwrap()
+            .unwrap();
+        assert!(diff.contains("--- live/my-devnet"));
+        assert!(diff.contains("+++ applied/my-devnet"));
+        assert!(diff.contains("-  seed: '42'"));
+        assert!(diff.contains("+  seed: '43'"));
+    }
+
+    #[test]
+    fn only_objects_applied_by_the_cli_are_pruned() {
+        let mut devnet = object(json!({
+            "apiVersion": "ryogoku.stark/v1",
+            "kind": "Devnet",
+            "metadata": { "name": "my-devnet" },
+        }));
+        assert!(!applied_by_cli(&devnet));
+
+        devnet.metadata.managed_fields = Some(vec![ManagedFieldsEntry {
+            manager: Some("kubectl".to_string()),
+            ..ManagedFieldsEntry::default()
+        }]);
+        assert!(!applied_by_cli(&devnet));
+
+        devnet
+            .metadata
+            .managed_fields
+            .as_mut()
+            .unwrap()
+            .push(ManagedFieldsEntry {
+                manager: Some(FIELD_MANAGER.to_string()),
+                ..ManagedFieldsEntry::default()
+            });
+        assert!(applied_by_cli(&devnet));
+    }
+}
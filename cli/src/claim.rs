@@ -0,0 +1,67 @@
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use ryogoku_operator::{
+    kube::{
+        api::{Api, DeleteParams, ObjectMeta, PostParams},
+        runtime::wait::await_condition,
+        ResourceExt,
+    },
+    DevnetClaim, DevnetClaimPhase, DevnetClaimSpec,
+};
+
+/// Claim a devnet from the pool and wait until it is bound.
+///
+/// Prints the name of the bound devnet. The claim is named after the pool unless `name` is
+/// given, and deleted if it isn't bound before the timeout.
+pub async fn claim(
+    claims: Api<DevnetClaim>,
+    namespace: &str,
+    pool: &str,
+    name: Option<String>,
+    timeout: Duration,
+) -> Result<()> {
+    let generate_name = name.is_none().then(|| format!("{}-", pool));
+    let data = DevnetClaim {
+        metadata: ObjectMeta {
+            name,
+            generate_name,
+            namespace: Some(namespace.to_string()),
+            ..ObjectMeta::default()
+        },
+        spec: DevnetClaimSpec {
+            pool: pool.to_string(),
+        },
+        status: None,
+    };
+    let claim = claims.create(&PostParams::default(), &data).await?;
+    let claim_name = claim.name_any();
+    eprintln!("claim {} created", claim_name);
+
+    let cond = |claim: Option<&DevnetClaim>| {
+        claim
+            .and_then(|claim| claim.status.as_ref())
+            .map(|status| status.phase == DevnetClaimPhase::Bound)
+            .unwrap_or(false)
+    };
+    let bound =
+        tokio::time::timeout(timeout, await_condition(claims.clone(), &claim_name, cond)).await;
+    let claim = match bound {
+        Ok(claim) => claim?.ok_or_else(|| anyhow!("claim {} not found", claim_name))?,
+        Err(_) => {
+            // don't leave the claim pending, it would bind a devnet nobody uses.
+            claims.delete(&claim_name, &DeleteParams::default()).await?;
+            return Err(anyhow!(
+                "timed out after {} waiting for claim {} to be bound, claim deleted",
+                humantime::format_duration(timeout),
+                claim_name
+            ));
+        }
+    };
+
+    let devnet = claim
+        .devnet()
+        .ok_or_else(|| anyhow!("claim {} has no devnet", claim_name))?;
+    println!("{}", devnet);
+    Ok(())
+}
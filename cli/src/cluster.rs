@@ -0,0 +1,89 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::Args;
+use ryogoku_operator::kube::{
+    config::{KubeConfigOptions, Kubeconfig},
+    Client, Config,
+};
+
+/// Options selecting the cluster and namespace, shared by all commands.
+#[derive(Args, Debug)]
+pub struct ClusterArgs {
+    /// Path to the kubeconfig file to use.
+    #[arg(long, global = true)]
+    kubeconfig: Option<PathBuf>,
+    /// Name of the kubeconfig context to use.
+    #[arg(long, global = true)]
+    context: Option<String>,
+    /// Namespace to use. Defaults to the namespace of the kubeconfig context.
+    #[arg(short, long, global = true, env = "RYOGOKU_NAMESPACE")]
+    namespace: Option<String>,
+}
+
+/// Client connected to the selected cluster.
+#[derive(Clone)]
+pub struct Cluster {
+    /// kube client.
+    pub client: Client,
+    /// Selected namespace.
+    pub namespace: String,
+}
+
+impl ClusterArgs {
+    /// Connect to the selected cluster.
+    pub async fn connect(&self) -> Result<Cluster> {
+        let options = KubeConfigOptions {
+            context: self.context.clone(),
+            ..KubeConfigOptions::default()
+        };
+
+        let config = match &self.kubeconfig {
+            Some(path) => {
+                let kubeconfig = Kubeconfig::read_from(path)?;
+                Config::from_custom_kubeconfig(kubeconfig, &options).await?
+            }
+            None if self.context.is_some() => Config::from_kubeconfig(&options).await?,
+            None => Config::infer().await?,
+        };
+
+        let namespace = self
+            .namespace
+            .clone()
+            .unwrap_or_else(|| config.default_namespace.clone());
+        let client = Client::try_from(config)?;
+
+        Ok(Cluster { client, namespace })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+    use crate::RyogokuCli;
+
+    #[test]
+    fn cluster_options_are_global() {
+        let cli = RyogokuCli::try_parse_from([
+            "ryogoku",
+            "devnet",
+            "list",
+            "--kubeconfig",
+            "/tmp/kubeconfig",
+            "--context",
+            "kind-dev",
+            "-n",
+            "qa",
+        ])
+        .unwrap();
+
+        assert_eq!(
+            cli.cluster.kubeconfig,
+            Some(PathBuf::from("/tmp/kubeconfig"))
+        );
+        assert_eq!(cli.cluster.context.as_deref(), Some("kind-dev"));
+        assert_eq!(cli.cluster.namespace.as_deref(), Some("qa"));
+    }
+}
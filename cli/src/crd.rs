@@ -0,0 +1,230 @@
+use anyhow::{bail, Context, Result};
+use ryogoku_operator::{
+    k8s_openapi::apiextensions_apiserver::pkg::apis::apiextensions::v1::CustomResourceDefinition,
+    kube::{
+        api::{Api, DeleteParams, ListParams, Patch, PatchParams, PostParams},
+        ResourceExt,
+    },
+    Devnet,
+};
+use similar::TextDiff;
+
+/// Returns the diff between the installed CRD schema and the one shipped with this binary,
+/// or `None` if they are the same.
+pub fn diff(
+    installed: &CustomResourceDefinition,
+    desired: &CustomResourceDefinition,
+) -> Result<Option<String>> {
+    let installed = normalized_spec(installed)?;
+    let desired = normalized_spec(desired)?;
+
+    if installed == desired {
+        return Ok(None);
+    }
+
+    let diff = TextDiff::from_lines(&installed, &desired)
+        .unified_diff()
+        .header("installed", "ryogoku")
+        .to_string();
+    Ok(Some(diff))
+}
+
+/// Serialize the CRD spec, without the fields defaulted by the api server.
+fn normalized_spec(crd: &CustomResourceDefinition) -> Result<String> {
+    let mut spec = crd.spec.clone();
+    spec.conversion = None;
+    Ok(serde_yaml::to_string(&spec)?)
+}
+
+/// Returns a version used to store objects of the installed CRD that the desired one removes.
+fn removed_stored_version(
+    installed: &CustomResourceDefinition,
+    desired: &CustomResourceDefinition,
+) -> Option<String> {
+    installed
+        .status
+        .as_ref()
+        .and_then(|status| status.stored_versions.clone())
+        .unwrap_or_default()
+        .into_iter()
+        .find(|version| !desired.spec.versions.iter().any(|v| &v.name == version))
+}
+
+/// Install the CRD, doing nothing if it already exists.
+pub async fn install(
+    crds: &Api<CustomResourceDefinition>,
+    desired: &CustomResourceDefinition,
+    dry_run: bool,
+) -> Result<()> {
+    let crd_name = desired.name_any();
+    let existing = crds.get_opt(&crd_name).await?;
+
+    if let Some(existing) = existing {
+        println!("CRD {} already exists.", crd_name);
+        if diff(&existing, desired)?.is_some() {
+            println!("The installed schema is outdated, upgrade it with `ryogoku crd upgrade`.");
+        }
+        return Ok(());
+    }
+
+    let opts = PostParams {
+        dry_run,
+        ..Default::default()
+    };
+
+    println!("Creating CRD {}...", crd_name);
+    crds.create(&opts, desired)
+        .await
+        .with_context(|| format!("failed to install CRD {}", crd_name))?;
+
+    println!(" 📦 CRD installed.");
+    Ok(())
+}
+
+/// Server-side apply the CRD shipped with this binary.
+///
+/// Refuses to remove versions that are still used to store objects, and validates the
+/// new CRD with a dry run before applying it.
+pub async fn upgrade(
+    crds: &Api<CustomResourceDefinition>,
+    desired: &CustomResourceDefinition,
+    dry_run: bool,
+) -> Result<()> {
+    let crd_name = desired.name_any();
+
+    let existing = match crds.get_opt(&crd_name).await? {
+        Some(existing) => existing,
+        None => return install(crds, desired, dry_run).await,
+    };
+
+    let diff = match diff(&existing, desired)? {
+        Some(diff) => diff,
+        None => {
+            println!("CRD {} is up to date.", crd_name);
+            return Ok(());
+        }
+    };
+
+    if let Some(version) = removed_stored_version(&existing, desired) {
+        bail!(
+            "CRD {} version {} is still used to store objects and would be removed",
+            crd_name,
+            version
+        );
+    }
+
+    print!("{}", diff);
+
+    let pp = PatchParams::apply("ryogoku").force();
+    crds.patch(&crd_name, &pp.clone().dry_run(), &Patch::Apply(desired))
+        .await
+        .with_context(|| format!("CRD {} failed validation", crd_name))?;
+
+    if dry_run {
+        println!("CRD {} can be upgraded (dry run).", crd_name);
+        return Ok(());
+    }
+
+    crds.patch(&crd_name, &pp, &Patch::Apply(desired))
+        .await
+        .with_context(|| format!("failed to upgrade CRD {}", crd_name))?;
+    println!(" 📦 CRD {} upgraded.", crd_name);
+    Ok(())
+}
+
+/// Delete the CRDs. Deleting the CRDs deletes all devnets, so it requires `force` if any exist.
+pub async fn uninstall(
+    crds: &Api<CustomResourceDefinition>,
+    devnets: &Api<Devnet>,
+    desired: &[CustomResourceDefinition],
+    force: bool,
+    dry_run: bool,
+) -> Result<()> {
+    let mut installed = Vec::new();
+    for crd in desired {
+        let crd_name = crd.name_any();
+        if crds.get_opt(&crd_name).await?.is_none() {
+            println!("CRD {} is not installed.", crd_name);
+        } else {
+            installed.push(crd_name);
+        }
+    }
+
+    if installed.is_empty() {
+        return Ok(());
+    }
+
+    // the devnet CRD may already be gone, in which case there is no devnet left.
+    let existing = devnets
+        .list(&ListParams::default())
+        .await
+        .map(|list| list.items)
+        .unwrap_or_default();
+    if !existing.is_empty() {
+        println!(
+            "⚠️  {} devnets exist, uninstalling the CRDs deletes all of them.",
+            existing.len()
+        );
+        if !force {
+            bail!("refusing to uninstall CRDs without --force");
+        }
+    }
+
+    let dp = DeleteParams {
+        dry_run,
+        ..DeleteParams::default()
+    };
+    for crd_name in installed {
+        crds.delete(&crd_name, &dp)
+            .await
+            .with_context(|| format!("failed to uninstall CRD {}", crd_name))?;
+        println!(" 🗑  CRD {} uninstalled.", crd_name);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use ryogoku_operator::{
+        k8s_openapi::apiextensions_apiserver::pkg::apis::apiextensions::v1::{
+            CustomResourceConversion, CustomResourceDefinitionStatus,
+        },
+        kube::CustomResourceExt,
+    };
+
+    use super::*;
+
+    #[test]
+    fn diff_ignores_the_defaulted_conversion() {
+        let desired = Devnet::crd();
+        let mut installed = desired.clone();
+        installed.spec.conversion = Some(CustomResourceConversion {
+            strategy: "None".to_string(),
+            webhook: None,
+        });
+        assert_eq!(diff(&installed, &desired).unwrap(), None);
+
+        installed.spec.versions[0].served = false;
+        let diff = diff(&installed, &desired).unwrap().unwrap();
+        assert!(diff.contains("--- installed"));
+        assert!(diff.contains("+++ ryogoku"));
+        assert!(diff.contains("-  served: false"));
+        assert!(diff.contains("+  served: true"));
+    }
+
+    #[test]
+    fn stored_versions_must_be_kept() {
+        let desired = Devnet::crd();
+        let mut installed = desired.clone();
+        assert_eq!(removed_stored_version(&installed, &desired), None);
+
+        installed.status = Some(CustomResourceDefinitionStatus {
+            stored_versions: Some(vec!["v1alpha1".to_string(), "v1".to_string()]),
+            ..CustomResourceDefinitionStatus::default()
+        });
+        assert_eq!(
+            removed_stored_version(&installed, &desired).as_deref(),
+            Some("v1alpha1")
+        );
+    }
+}
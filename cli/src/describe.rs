@@ -0,0 +1,438 @@
+use std::fmt::Write;
+
+use anyhow::Result;
+use ryogoku_operator::{
+    k8s_openapi::{
+        api::core::v1::{Endpoints, Event, Pod, Service},
+        apimachinery::pkg::apis::meta::v1::Time,
+    },
+    kube::{
+        api::{Api, ListParams},
+        Client, ResourceExt,
+    },
+    Devnet,
+};
+
+use crate::{
+    devnet::{time_ago, HumanReadable},
+    pod::devnet_pod,
+    template::merge_templates,
+};
+
+/// Print a detailed, human readable description of the devnet and its owned resources.
+pub async fn describe(client: Client, namespace: &str, name: &str) -> Result<()> {
+    let devnets: Api<Devnet> = Api::namespaced(client.clone(), namespace);
+    let pods: Api<Pod> = Api::namespaced(client.clone(), namespace);
+    let services: Api<Service> = Api::namespaced(client.clone(), namespace);
+    let endpoints: Api<Endpoints> = Api::namespaced(client.clone(), namespace);
+    let events: Api<Event> = Api::namespaced(client.clone(), namespace);
+
+    // describe the spec the operator runs, with the template fields.
+    let devnet = devnets.get(name).await?;
+    let devnet = merge_templates(client, vec![devnet]).await?.remove(0);
+    let pod = devnet_pod(&pods, name).await.ok();
+    let service = services.get_opt(name).await?;
+    let service_endpoints = endpoints.get_opt(name).await?;
+    // events of the current devnet and of its pod and service, not of older objects with the
+    // same name.
+    let involved = [
+        ("Devnet", devnet.name_any(), devnet.uid()),
+        (
+            "Pod",
+            pod.as_ref().map(|p| p.name_any()).unwrap_or_default(),
+            pod.as_ref().and_then(|p| p.uid()),
+        ),
+        (
+            "Service",
+            name.to_string(),
+            service.as_ref().and_then(|s| s.uid()),
+        ),
+    ];
+    let mut object_events = Vec::new();
+    for (kind, object_name, uid) in involved {
+        let uid = match uid {
+            Some(uid) => uid,
+            None => continue,
+        };
+        let lp = ListParams::default().fields(&format!(
+            "involvedObject.kind={},involvedObject.name={},involvedObject.uid={}",
+            kind, object_name, uid
+        ));
+        object_events.extend(events.list(&lp).await?.items);
+    }
+    let mut events = object_events;
+    events.sort_by_key(last_seen);
+
+    let mut out = String::new();
+    describe_devnet(&mut out, &devnet)?;
+    describe_pod(&mut out, pod.as_ref())?;
+    describe_service(&mut out, service.as_ref(), service_endpoints.as_ref())?;
+    describe_events(&mut out, &events)?;
+    print!("{}", out);
+
+    Ok(())
+}
+
+fn describe_devnet(out: &mut String, devnet: &Devnet) -> Result<()> {
+    let spec = &devnet.spec;
+    writeln!(out, "Name:          {}", devnet.name_any())?;
+    writeln!(
+        out,
+        "Namespace:     {}",
+        devnet.namespace().unwrap_or_default()
+    )?;
+    writeln!(
+        out,
+        "Created:       {}",
+        time(devnet.metadata.creation_timestamp.as_ref())
+    )?;
+    writeln!(out, "State:         {}", devnet.state())?;
+
+    writeln!(out, "Spec:")?;
+    let spec_yaml = serde_yaml::to_string(spec)?;
+    for line in spec_yaml.lines().filter(|l| !l.ends_with(": null")) {
+        writeln!(out, "  {}", line)?;
+    }
+
+    writeln!(out, "Accounts:")?;
+    writeln!(out, "  Count:            {}", or_default(spec.accounts))?;
+    writeln!(
+        out,
+        "  Initial Balance:  {}",
+        or_default(spec.initial_balance.as_ref())
+    )?;
+    writeln!(
+        out,
+        "  Seed:             {}",
+        or_default(spec.seed.as_ref())
+    )?;
+
+    let status = devnet.status.clone().unwrap_or_default();
+    writeln!(out, "Endpoints:")?;
+    match &status.endpoints {
+        Some(endpoints) => {
+            writeln!(out, "  RPC:               {}", endpoints.rpc)?;
+            writeln!(out, "  Gateway:           {}", endpoints.gateway)?;
+            writeln!(
+                out,
+                "  External RPC:      {}",
+                or_none(endpoints.external_rpc.as_ref())
+            )?;
+            writeln!(
+                out,
+                "  External Gateway:  {}",
+                or_none(endpoints.external_gateway.as_ref())
+            )?;
+        }
+        None => writeln!(out, "  <none>")?,
+    }
+    if let Some(public_url) = &status.public_url {
+        writeln!(out, "  Public URL:        {}", public_url)?;
+    }
+
+    if let Some(cloned_from) = &status.cloned_from {
+        writeln!(out, "Cloned From:   {}", cloned_from)?;
+    }
+
+    if let Some(restart_count) = status.restart_count {
+        writeln!(
+            out,
+            "Restarts:      {} (last {} ago)",
+            restart_count,
+            time_ago_or_none(status.last_restart_time.as_ref())
+        )?;
+    }
+
+    if let Some(expires_at) = &status.expires_at {
+        writeln!(out, "Expires:       {}", expires_at.0.to_rfc3339())?;
+    }
+
+    if let Some(health) = &status.health {
+        writeln!(out, "Health:")?;
+        writeln!(out, "  Block Number:  {}", or_none(health.block_number))?;
+        writeln!(
+            out,
+            "  Chain Id:      {}",
+            or_none(health.chain_id.as_ref())
+        )?;
+        writeln!(
+            out,
+            "  RPC Version:   {}",
+            or_none(health.rpc_version.as_ref())
+        )?;
+        writeln!(
+            out,
+            "  Image Tag:     {}",
+            or_none(health.image_tag.as_ref())
+        )?;
+        let last_seen = health
+            .last_seen_time
+            .as_ref()
+            .map(|t| format!("{} ago", time_ago(t).to_human()));
+        writeln!(out, "  Last Seen:     {}", or_none(last_seen))?;
+    }
+
+    if let Some(bootstrap) = &status.bootstrap {
+        writeln!(out, "Bootstrap:")?;
+        for step in &bootstrap.steps {
+            writeln!(
+                out,
+                "  {:<16} {:<10} {}",
+                step.name,
+                format!("{:?}", step.phase),
+                step.message.as_deref().unwrap_or_default(),
+            )?;
+            let results = step
+                .class_hashes
+                .iter()
+                .flatten()
+                .chain(step.addresses.iter().flatten());
+            for (name, value) in results {
+                writeln!(out, "    {:<14} {}", name, value)?;
+            }
+        }
+    }
+
+    if let Some(l1) = &status.l1 {
+        writeln!(out, "L1:")?;
+        writeln!(out, "  RPC:                 {}", l1.rpc)?;
+        writeln!(
+            out,
+            "  Messaging Contract:  {}",
+            or_none(l1.messaging_contract_address.as_ref())
+        )?;
+    }
+
+    if let Some(dojo) = &status.dojo {
+        writeln!(out, "Dojo:")?;
+        writeln!(
+            out,
+            "  World:          {}",
+            or_none(dojo.world_address.as_ref())
+        )?;
+        writeln!(
+            out,
+            "  Torii GraphQL:  {}",
+            or_none(dojo.torii_graphql.as_ref())
+        )?;
+    }
+
+    writeln!(out, "Conditions:")?;
+    let conditions = status.conditions.unwrap_or_default();
+    if conditions.is_empty() {
+        writeln!(out, "  <none>")?;
+    }
+    for condition in conditions {
+        writeln!(
+            out,
+            "  {:<16} {:<8} {:<20} {:<6} {}",
+            condition.type_,
+            condition.status,
+            condition.reason.unwrap_or_default(),
+            time_ago_or_none(condition.last_transition_time.as_ref()),
+            condition.message.unwrap_or_default(),
+        )?;
+    }
+
+    Ok(())
+}
+
+fn describe_pod(out: &mut String, pod: Option<&Pod>) -> Result<()> {
+    writeln!(out, "Pod:")?;
+    let pod = match pod {
+        Some(pod) => pod,
+        None => {
+            writeln!(out, "  <none>")?;
+            return Ok(());
+        }
+    };
+
+    let status = pod.status.clone().unwrap_or_default();
+    let node = pod.spec.as_ref().and_then(|s| s.node_name.as_ref());
+    writeln!(out, "  Name:     {}", pod.name_any())?;
+    writeln!(out, "  Phase:    {}", or_none(status.phase.as_ref()))?;
+    writeln!(out, "  Node:     {}", or_none(node))?;
+    writeln!(out, "  IP:       {}", or_none(status.pod_ip.as_ref()))?;
+    writeln!(out, "  Started:  {}", time(status.start_time.as_ref()))?;
+    writeln!(out, "  Containers:")?;
+    for container in status.container_statuses.unwrap_or_default() {
+        let state = container.state.unwrap_or_default();
+        let state = if let Some(running) = state.running {
+            format!("Running since {}", time(running.started_at.as_ref()))
+        } else if let Some(waiting) = state.waiting {
+            format!("Waiting ({})", waiting.reason.unwrap_or_default())
+        } else if let Some(terminated) = state.terminated {
+            format!(
+                "Terminated ({}, exit code {})",
+                terminated.reason.unwrap_or_default(),
+                terminated.exit_code
+            )
+        } else {
+            "Unknown".to_string()
+        };
+        writeln!(out, "    {}:", container.name)?;
+        writeln!(out, "      Image:     {}", container.image)?;
+        writeln!(out, "      State:     {}", state)?;
+        writeln!(out, "      Ready:     {}", container.ready)?;
+        writeln!(out, "      Restarts:  {}", container.restart_count)?;
+    }
+
+    Ok(())
+}
+
+fn describe_service(
+    out: &mut String,
+    service: Option<&Service>,
+    endpoints: Option<&Endpoints>,
+) -> Result<()> {
+    writeln!(out, "Service:")?;
+    let service = match service {
+        Some(service) => service,
+        None => {
+            writeln!(out, "  <none>")?;
+            return Ok(());
+        }
+    };
+
+    let spec = service.spec.clone().unwrap_or_default();
+    writeln!(out, "  Name:        {}", service.name_any())?;
+    writeln!(out, "  Type:        {}", or_none(spec.type_.as_ref()))?;
+    writeln!(out, "  Cluster IP:  {}", or_none(spec.cluster_ip.as_ref()))?;
+    let lb_ingress: Vec<_> = service
+        .status
+        .as_ref()
+        .and_then(|s| s.load_balancer.as_ref())
+        .and_then(|lb| lb.ingress.clone())
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|ingress| ingress.hostname.or(ingress.ip))
+        .collect();
+    if !lb_ingress.is_empty() {
+        writeln!(out, "  Load Balancer:  {}", lb_ingress.join(", "))?;
+    }
+    writeln!(out, "  Ports:")?;
+    for port in spec.ports.unwrap_or_default() {
+        let node_port = port
+            .node_port
+            .map(|p| format!(" (node port {})", p))
+            .unwrap_or_default();
+        writeln!(
+            out,
+            "    {}  {}/{}{}",
+            port.name.unwrap_or_default(),
+            port.port,
+            port.protocol.unwrap_or_else(|| "TCP".to_string()),
+            node_port,
+        )?;
+    }
+    let addresses: Vec<_> = endpoints
+        .and_then(|e| e.subsets.clone())
+        .unwrap_or_default()
+        .into_iter()
+        .flat_map(|subset| subset.addresses.unwrap_or_default())
+        .map(|address| address.ip)
+        .collect();
+    if addresses.is_empty() {
+        writeln!(out, "  Endpoints:   <none>")?;
+    } else {
+        writeln!(out, "  Endpoints:   {}", addresses.join(", "))?;
+    }
+
+    Ok(())
+}
+
+fn describe_events(out: &mut String, events: &[Event]) -> Result<()> {
+    writeln!(out, "Events:")?;
+    if events.is_empty() {
+        writeln!(out, "  <none>")?;
+        return Ok(());
+    }
+
+    writeln!(
+        out,
+        "  {:<8} {:<20} {:<8} {:<10} MESSAGE",
+        "TYPE", "REASON", "AGE", "OBJECT"
+    )?;
+    for event in events {
+        let last_seen = last_seen(event);
+        writeln!(
+            out,
+            "  {:<8} {:<20} {:<8} {:<10} {}",
+            event.type_.clone().unwrap_or_default(),
+            event.reason.clone().unwrap_or_default(),
+            time_ago_or_none(last_seen.as_ref()),
+            event.involved_object.kind.clone().unwrap_or_default(),
+            event.message.clone().unwrap_or_default(),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Returns the last time the event was seen. Events reported with the `events.k8s.io` api
+/// only have an event time.
+fn last_seen(event: &Event) -> Option<Time> {
+    event
+        .last_timestamp
+        .clone()
+        .or_else(|| event.event_time.as_ref().map(|t| Time(t.0)))
+        .or_else(|| event.first_timestamp.clone())
+}
+
+fn time(time: Option<&Time>) -> String {
+    match time {
+        Some(t) => format!("{} ({} ago)", t.0.to_rfc3339(), time_ago(t).to_human()),
+        None => "<none>".to_string(),
+    }
+}
+
+fn time_ago_or_none(time: Option<&Time>) -> String {
+    time.map(|t| time_ago(t).to_human())
+        .unwrap_or_else(|| "<none>".to_string())
+}
+
+fn or_none<T: ToString>(value: Option<T>) -> String {
+    value
+        .map(|v| v.to_string())
+        .unwrap_or_else(|| "<none>".to_string())
+}
+
+fn or_default<T: ToString>(value: Option<T>) -> String {
+    value
+        .map(|v| v.to_string())
+        .unwrap_or_else(|| "<default>".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use ryogoku_operator::k8s_openapi::{
+        apimachinery::pkg::apis::meta::v1::MicroTime, chrono::TimeZone, chrono::Utc,
+    };
+
+    use super::*;
+
+    #[test]
+    fn last_seen_falls_back_to_the_event_time() {
+        let at = |minute| Utc.with_ymd_and_hms(2023, 3, 1, 10, minute, 0).unwrap();
+        let event = Event {
+            first_timestamp: Some(Time(at(1))),
+            event_time: Some(MicroTime(at(2))),
+            last_timestamp: Some(Time(at(3))),
+            ..Event::default()
+        };
+        assert_eq!(last_seen(&event), Some(Time(at(3))));
+
+        let event = Event {
+            last_timestamp: None,
+            ..event
+        };
+        assert_eq!(last_seen(&event), Some(Time(at(2))));
+
+        let event = Event {
+            event_time: None,
+            ..event
+        };
+        assert_eq!(last_seen(&event), Some(Time(at(1))));
+        assert_eq!(last_seen(&Event::default()), None);
+    }
+}
@@ -17,7 +17,7 @@ impl DevnetOut {
 }
 
 impl Tabled for DevnetOut {
-    const LENGTH: usize = 4;
+    const LENGTH: usize = 7;
 
     fn fields(&self) -> Vec<Cow<'_, str>> {
         let inner = &self.0;
@@ -30,11 +30,27 @@ impl Tabled for DevnetOut {
             .as_ref()
             .map(|t| time_ago(t).to_human())
             .unwrap_or_default();
+        let endpoints = inner.status.as_ref().and_then(|s| s.endpoints.as_ref());
+        let rpc = endpoints
+            .map(|e| e.preferred_rpc().to_string())
+            .unwrap_or_default();
+        let gateway = endpoints
+            .map(|e| e.preferred_gateway().to_string())
+            .unwrap_or_default();
+        let expires = inner
+            .status
+            .as_ref()
+            .and_then(|s| s.expires_at.as_ref())
+            .map(|t| format!("in {}", time_until(t).to_human()))
+            .unwrap_or_else(|| "-".to_string());
 
         vec![
             Cow::Owned(namespace),
             Cow::Owned(name),
             Cow::Owned(state),
+            Cow::Owned(rpc),
+            Cow::Owned(gateway),
+            Cow::Owned(expires),
             Cow::Owned(age),
         ]
     }
@@ -44,16 +60,82 @@ impl Tabled for DevnetOut {
             Cow::Owned("NAMESPACE".to_string()),
             Cow::Owned("NAME".to_string()),
             Cow::Owned("STATE".to_string()),
+            Cow::Owned("RPC".to_string()),
+            Cow::Owned("GATEWAY".to_string()),
+            Cow::Owned("EXPIRES".to_string()),
             Cow::Owned("AGE".to_string()),
         ]
     }
 }
 
-fn time_ago(time: &Time) -> Duration {
+/// Wrapper around [Devnet] and the node running it, used to implement a wide [Tabled].
+pub struct DevnetWideOut(Devnet, Option<String>);
+
+impl DevnetWideOut {
+    pub fn new(devnet: Devnet, node: Option<String>) -> Self {
+        DevnetWideOut(devnet, node)
+    }
+}
+
+impl Tabled for DevnetWideOut {
+    const LENGTH: usize = DevnetOut::LENGTH + 4;
+
+    fn fields(&self) -> Vec<Cow<'_, str>> {
+        let inner = &self.0;
+        let mut fields: Vec<_> = DevnetOut(inner.clone())
+            .fields()
+            .into_iter()
+            .map(|f| Cow::Owned(f.into_owned()))
+            .collect();
+        let age = fields.pop().unwrap_or_default();
+
+        let image = inner.spec.image.clone().unwrap_or_else(|| "-".to_string());
+        let accounts = inner
+            .spec
+            .accounts
+            .map(|a| a.to_string())
+            .unwrap_or_else(|| "-".to_string());
+        let service_type = inner
+            .spec
+            .service_type
+            .clone()
+            .unwrap_or_else(|| "ClusterIP".to_string());
+        let node = self.1.clone().unwrap_or_else(|| "-".to_string());
+
+        fields.extend([
+            Cow::Owned(image),
+            Cow::Owned(accounts),
+            Cow::Owned(service_type),
+            Cow::Owned(node),
+            age,
+        ]);
+        fields
+    }
+
+    fn headers() -> Vec<Cow<'static, str>> {
+        let mut headers = DevnetOut::headers();
+        let age = headers.pop().unwrap_or_default();
+        headers.extend([
+            Cow::Owned("IMAGE".to_string()),
+            Cow::Owned("ACCOUNTS".to_string()),
+            Cow::Owned("SERVICE-TYPE".to_string()),
+            Cow::Owned("NODE".to_string()),
+            age,
+        ]);
+        headers
+    }
+}
+
+pub fn time_ago(time: &Time) -> Duration {
     let now = Utc::now();
     now - time.0
 }
 
+pub fn time_until(time: &Time) -> Duration {
+    let now = Utc::now();
+    (time.0 - now).max(Duration::zero())
+}
+
 pub trait HumanReadable {
     fn to_human(&self) -> String;
 }
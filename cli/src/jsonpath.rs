@@ -0,0 +1,196 @@
+//! Minimal implementation of kubectl JSONPath templates.
+//!
+//! Supports literal text, `{.field.nested}` paths, `[n]` indexes, `[*]` wildcards and
+//! quoted string literals such as `{"\n"}`.
+
+use anyhow::{anyhow, bail, Result};
+use serde_json::Value;
+
+/// A parsed JSONPath template.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Template(Vec<Part>);
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Part {
+    Text(String),
+    Path(Vec<Segment>),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Segment {
+    Field(String),
+    Index(usize),
+    Wildcard,
+}
+
+impl Template {
+    /// Parse a template like `{.items[*].metadata.name}`.
+    pub fn parse(template: &str) -> Result<Template> {
+        let mut parts = Vec::new();
+        let mut rest = template;
+
+        while !rest.is_empty() {
+            match rest.find('{') {
+                Some(0) => {
+                    let end = expression_end(rest)
+                        .ok_or_else(|| anyhow!("unclosed expression in jsonpath {}", template))?;
+                    parts.push(parse_expression(rest[1..end].trim())?);
+                    rest = &rest[end + 1..];
+                }
+                Some(start) => {
+                    parts.push(Part::Text(rest[..start].to_string()));
+                    rest = &rest[start..];
+                }
+                None => {
+                    parts.push(Part::Text(rest.to_string()));
+                    rest = "";
+                }
+            }
+        }
+
+        Ok(Template(parts))
+    }
+
+    /// Render the template against the value.
+    pub fn render(&self, value: &Value) -> String {
+        let mut out = String::new();
+        for part in &self.0 {
+            match part {
+                Part::Text(text) => out.push_str(text),
+                Part::Path(segments) => {
+                    let values: Vec<_> = select(value, segments).into_iter().map(to_text).collect();
+                    out.push_str(&values.join(" "));
+                }
+            }
+        }
+        out
+    }
+}
+
+/// Returns the index of the `}` closing the expression at the start of `s`, skipping the
+/// braces in quoted literals.
+fn expression_end(s: &str) -> Option<usize> {
+    let mut quoted = false;
+    let mut escaped = false;
+    for (index, c) in s.char_indices().skip(1) {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            '}' if !quoted => return Some(index),
+            _ => {}
+        }
+    }
+    None
+}
+
+fn parse_expression(expr: &str) -> Result<Part> {
+    if let Some(literal) = expr.strip_prefix('"').and_then(|e| e.strip_suffix('"')) {
+        let text = literal
+            .replace("\\n", "\n")
+            .replace("\\t", "\t")
+            .replace("\\\"", "\"");
+        return Ok(Part::Text(text));
+    }
+
+    let mut segments = Vec::new();
+    let mut rest = expr.strip_prefix('$').unwrap_or(expr);
+
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('.') {
+            let end = after.find(['.', '[']).unwrap_or(after.len());
+            if end > 0 {
+                segments.push(Segment::Field(after[..end].to_string()));
+            }
+            rest = &after[end..];
+        } else if let Some(after) = rest.strip_prefix('[') {
+            let end = after
+                .find(']')
+                .ok_or_else(|| anyhow!("unclosed bracket in jsonpath {}", expr))?;
+            let index = &after[..end];
+            if index == "*" {
+                segments.push(Segment::Wildcard);
+            } else {
+                segments.push(Segment::Index(index.parse()?));
+            }
+            rest = &after[end + 1..];
+        } else {
+            bail!("invalid jsonpath expression {}", expr);
+        }
+    }
+
+    Ok(Part::Path(segments))
+}
+
+fn select<'a>(value: &'a Value, segments: &[Segment]) -> Vec<&'a Value> {
+    let (segment, rest) = match segments.split_first() {
+        Some(split) => split,
+        None => return vec![value],
+    };
+
+    match segment {
+        Segment::Field(field) => value
+            .get(field)
+            .map(|v| select(v, rest))
+            .unwrap_or_default(),
+        Segment::Index(index) => value
+            .get(index)
+            .map(|v| select(v, rest))
+            .unwrap_or_default(),
+        Segment::Wildcard => match value {
+            Value::Array(items) => items.iter().flat_map(|v| select(v, rest)).collect(),
+            Value::Object(map) => map.values().flat_map(|v| select(v, rest)).collect(),
+            _ => Vec::default(),
+        },
+    }
+}
+
+fn to_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn render(template: &str, value: &Value) -> String {
+        Template::parse(template).unwrap().render(value)
+    }
+
+    #[test]
+    fn renders_fields_and_text() {
+        let value = json!({ "metadata": { "name": "my-devnet" } });
+        assert_eq!(render("name: {.metadata.name}", &value), "name: my-devnet");
+        assert_eq!(render("{$.metadata.name}", &value), "my-devnet");
+    }
+
+    #[test]
+    fn renders_indexes_and_wildcards() {
+        let value = json!({ "items": [{ "name": "a" }, { "name": "b" }] });
+        assert_eq!(render("{.items[1].name}", &value), "b");
+        assert_eq!(render("{.items[*].name}", &value), "a b");
+        assert_eq!(render("{.items[5].name}", &value), "");
+    }
+
+    #[test]
+    fn renders_literals() {
+        let value = json!({ "a": 1, "b": true });
+        assert_eq!(render(r#"{.a}{"\n"}{.b}"#, &value), "1\ntrue");
+        assert_eq!(render(r#"{"}"}"#, &value), "}");
+        assert_eq!(render(r#"{"\"}"}{.a}"#, &value), "\"}1");
+    }
+
+    #[test]
+    fn rejects_invalid_templates() {
+        assert!(Template::parse("{.metadata").is_err());
+        assert!(Template::parse("{.items[0}").is_err());
+        assert!(Template::parse("{.items[x]}").is_err());
+        assert!(Template::parse("{metadata}").is_err());
+    }
+}
@@ -0,0 +1,309 @@
+use std::{
+    fs::{self, File},
+    io::{BufRead, BufReader, IsTerminal, Seek, SeekFrom},
+    net::TcpListener,
+    os::unix::process::CommandExt,
+    path::PathBuf,
+    process::{Command, Stdio},
+    time::Duration,
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::Utc;
+use ryogoku_operator::{
+    k8s_openapi::apimachinery::pkg::apis::meta::v1::Time, kube::api::ObjectMeta, Devnet,
+    DevnetEndpoints, DevnetSpec, DevnetState, DevnetStatus,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::logs::{print_line, LogsOptions};
+
+/// Namespace reported for local devnets.
+static LOCAL_NAMESPACE: &str = "local";
+static DEFAULT_DEVNET_BIN: &str = "starknet-devnet";
+/// How long a new devnet process must stay up to be considered started.
+const START_GRACE_PERIOD: Duration = Duration::from_millis(500);
+/// How often followed logs are polled for new lines.
+const LOG_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Devnet running as a local process, stored in `devnet.json` in its state directory.
+#[derive(Deserialize, Serialize)]
+struct LocalDevnet {
+    name: String,
+    spec: DevnetSpec,
+    pid: u32,
+    /// Start time of the process, in clock ticks after boot. Only known on Linux.
+    start_time: Option<u64>,
+    port: u16,
+    created_at: Time,
+}
+
+impl LocalDevnet {
+    /// Returns true if the devnet process is still running.
+    ///
+    /// The pid may have been reused by another process after the devnet exited, so the
+    /// process start time must match too when it's known.
+    fn is_running(&self) -> bool {
+        if let Some(start_time) = self.start_time {
+            return process_start_time(self.pid) == Some(start_time);
+        }
+
+        Command::new("kill")
+            .args(["-0", &self.pid.to_string()])
+            .stderr(Stdio::null())
+            .status()
+            .map(|status| status.success())
+            .unwrap_or(false)
+    }
+
+    /// Returns the devnet as a [Devnet], so it prints like the cluster ones.
+    fn to_devnet(&self) -> Devnet {
+        let state = if self.is_running() {
+            DevnetState::Running
+        } else {
+            DevnetState::Errored
+        };
+        let url = format!("http://127.0.0.1:{}", self.port);
+
+        Devnet {
+            metadata: ObjectMeta {
+                name: Some(self.name.clone()),
+                namespace: Some(LOCAL_NAMESPACE.to_string()),
+                creation_timestamp: Some(self.created_at.clone()),
+                ..ObjectMeta::default()
+            },
+            spec: self.spec.clone(),
+            status: Some(DevnetStatus {
+                state,
+                endpoints: Some(DevnetEndpoints {
+                    rpc: format!("{}/rpc", url),
+                    gateway: url,
+                    ..DevnetEndpoints::default()
+                }),
+                ..DevnetStatus::default()
+            }),
+        }
+    }
+}
+
+/// Devnets running as local processes, tracked in a state directory.
+///
+/// The directory is `$RYOGOKU_STATE_DIR`, or `ryogoku` in `$XDG_STATE_HOME` or
+/// `~/.local/state`. Every devnet has a directory with its state and its logs.
+pub struct LocalDevnets {
+    dir: PathBuf,
+}
+
+impl LocalDevnets {
+    pub fn open() -> Result<Self> {
+        let dir = match std::env::var_os("RYOGOKU_STATE_DIR") {
+            Some(dir) => PathBuf::from(dir),
+            None => {
+                let state_home = match std::env::var_os("XDG_STATE_HOME") {
+                    Some(dir) => PathBuf::from(dir),
+                    None => std::env::var_os("HOME")
+                        .map(|home| PathBuf::from(home).join(".local").join("state"))
+                        .ok_or_else(|| anyhow!("HOME is not set, set RYOGOKU_STATE_DIR"))?,
+                };
+                state_home.join("ryogoku")
+            }
+        };
+
+        Ok(LocalDevnets {
+            dir: dir.join("devnets"),
+        })
+    }
+
+    /// Start a devnet process with the same arguments as the devnet pods.
+    ///
+    /// The binary is `$RYOGOKU_DEVNET_BIN`, defaulting to `starknet-devnet`.
+    pub fn create(&self, name: &str, spec: DevnetSpec) -> Result<Devnet> {
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.')
+        {
+            bail!("invalid devnet name {}", name);
+        }
+
+        let dir = self.dir.join(name);
+        if dir.exists() {
+            bail!("devnet {} already exists", name);
+        }
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("failed to create state directory {}", dir.display()))?;
+
+        let port = TcpListener::bind("127.0.0.1:0")?.local_addr()?.port();
+        let log = File::create(dir.join("devnet.log"))?;
+
+        let bin =
+            std::env::var("RYOGOKU_DEVNET_BIN").unwrap_or_else(|_| DEFAULT_DEVNET_BIN.to_string());
+        let mut args = vec![
+            "--host".to_string(),
+            "127.0.0.1".to_string(),
+            "--port".to_string(),
+            port.to_string(),
+        ];
+        args.extend(spec.args(None));
+
+        // own process group, so the devnet doesn't get the signals sent to the cli.
+        let spawned = Command::new(&bin)
+            .args(&args)
+            .stdin(Stdio::null())
+            .stdout(log.try_clone()?)
+            .stderr(log)
+            .process_group(0)
+            .spawn();
+        let mut child = match spawned {
+            Ok(child) => child,
+            Err(err) => {
+                fs::remove_dir_all(&dir)?;
+                return Err(err).with_context(|| format!("failed to run {}", bin));
+            }
+        };
+
+        let devnet = LocalDevnet {
+            name: name.to_string(),
+            spec,
+            pid: child.id(),
+            start_time: process_start_time(child.id()),
+            port,
+            created_at: Time(Utc::now()),
+        };
+        fs::write(
+            dir.join("devnet.json"),
+            serde_json::to_string_pretty(&devnet)?,
+        )?;
+
+        std::thread::sleep(START_GRACE_PERIOD);
+        if let Some(status) = child.try_wait()? {
+            bail!(
+                "devnet {} exited with {}, see `ryogoku --local devnet logs {}`",
+                name,
+                status,
+                name
+            );
+        }
+
+        Ok(devnet.to_devnet())
+    }
+
+    /// Returns all the local devnets, sorted by name.
+    pub fn list(&self) -> Result<Vec<Devnet>> {
+        if !self.dir.exists() {
+            return Ok(Vec::default());
+        }
+
+        let mut names = fs::read_dir(&self.dir)?
+            .map(|entry| Ok(entry?.file_name().to_string_lossy().into_owned()))
+            .collect::<Result<Vec<_>>>()?;
+        names.sort();
+
+        names
+            .iter()
+            .map(|name| Ok(self.load(name)?.to_devnet()))
+            .collect()
+    }
+
+    pub fn get(&self, name: &str) -> Result<Devnet> {
+        Ok(self.load(name)?.to_devnet())
+    }
+
+    /// Stop the devnet process and remove its state directory.
+    pub fn delete(&self, name: &str) -> Result<()> {
+        let devnet = self.load(name)?;
+        if devnet.is_running() {
+            let status = Command::new("kill").arg(devnet.pid.to_string()).status()?;
+            if !status.success() {
+                bail!("failed to stop devnet {} with pid {}", name, devnet.pid);
+            }
+        }
+
+        fs::remove_dir_all(self.dir.join(name))?;
+        Ok(())
+    }
+
+    /// Print the logs of the devnet process.
+    ///
+    /// With `follow`, new lines are printed until the process exits.
+    pub async fn logs(&self, name: &str, opts: LogsOptions) -> Result<()> {
+        if opts.since.is_some() || opts.previous {
+            bail!("--since and --previous are not supported in local mode");
+        }
+
+        let devnet = self.load(name)?;
+        let color = opts.color && std::io::stdout().is_terminal();
+        let mut reader = BufReader::new(File::open(self.dir.join(name).join("devnet.log"))?);
+
+        let mut lines = Vec::default();
+        let mut line = String::default();
+        while reader.read_line(&mut line)? > 0 {
+            lines.push(line.trim_end().to_string());
+            line.clear();
+        }
+        let skip = match opts.tail {
+            Some(tail) => lines.len().saturating_sub(tail.max(0) as usize),
+            None => 0,
+        };
+        for line in &lines[skip..] {
+            print_line(line, color);
+        }
+
+        if !opts.follow {
+            return Ok(());
+        }
+
+        loop {
+            let running = devnet.is_running();
+            let position = reader.stream_position()?;
+            if reader.read_line(&mut line)? > 0 && line.ends_with('\n') {
+                print_line(line.trim_end(), color);
+                line.clear();
+                continue;
+            }
+            // wait for the rest of partial lines.
+            line.clear();
+            reader.seek(SeekFrom::Start(position))?;
+
+            if !running {
+                return Ok(());
+            }
+            tokio::time::sleep(LOG_POLL_INTERVAL).await;
+        }
+    }
+
+    fn load(&self, name: &str) -> Result<LocalDevnet> {
+        let path = self.dir.join(name).join("devnet.json");
+        let data =
+            fs::read_to_string(&path).map_err(|_| anyhow!("local devnet {} not found", name))?;
+        serde_json::from_str(&data)
+            .with_context(|| format!("invalid devnet state {}", path.display()))
+    }
+}
+
+/// Returns the start time of the process from `/proc`, in clock ticks after boot.
+fn process_start_time(pid: u32) -> Option<u64> {
+    let stat = fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    // the command name may contain spaces and parentheses, the other fields follow the last one.
+    let fields: Vec<_> = stat[stat.rfind(')')? + 1..].split_whitespace().collect();
+    // the start time is the 22nd field, the 20th after the command name.
+    fields.get(19)?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn process_start_time_is_stable() {
+        let start_time = process_start_time(std::process::id());
+        assert!(start_time.is_some());
+        assert_eq!(start_time, process_start_time(std::process::id()));
+    }
+
+    #[test]
+    fn process_start_time_is_none_without_process() {
+        assert_eq!(process_start_time(u32::MAX), None);
+    }
+}
@@ -0,0 +1,91 @@
+use std::io::IsTerminal;
+
+use anyhow::Result;
+use futures::{AsyncBufReadExt, TryStreamExt};
+use owo_colors::OwoColorize;
+use ryogoku_operator::{
+    k8s_openapi::api::core::v1::Pod,
+    kube::{
+        api::{Api, LogParams},
+        ResourceExt,
+    },
+};
+
+use crate::pod::devnet_pod;
+
+/// Options of the `devnet logs` command.
+pub struct LogsOptions {
+    pub follow: bool,
+    pub since: Option<std::time::Duration>,
+    pub previous: bool,
+    pub tail: Option<i64>,
+    pub color: bool,
+}
+
+/// Stream the logs of the devnet pod to stdout.
+pub async fn logs(pods: Api<Pod>, name: &str, opts: LogsOptions) -> Result<()> {
+    let pod = devnet_pod(&pods, name).await?;
+
+    let lp = LogParams {
+        container: Some("starknet-devnet".to_string()),
+        follow: opts.follow,
+        since_seconds: opts.since.map(|since| since.as_secs() as i64),
+        previous: opts.previous,
+        tail_lines: opts.tail,
+        ..LogParams::default()
+    };
+
+    let color = opts.color && std::io::stdout().is_terminal();
+
+    let mut lines = pods
+        .log_stream(&pod.name_any(), &lp)
+        .await?
+        .map_err(std::io::Error::other)
+        .into_async_read()
+        .lines();
+
+    while let Some(line) = lines.try_next().await? {
+        print_line(&line, color);
+    }
+
+    Ok(())
+}
+
+/// Print a line of the devnet output, highlighted if `color` is set.
+pub fn print_line(line: &str, color: bool) {
+    if color {
+        println!("{}", colorize(line));
+    } else {
+        println!("{}", line);
+    }
+}
+
+/// Highlight errors, transactions and blocks in the devnet output.
+fn colorize(line: &str) -> String {
+    let lower = line.to_lowercase();
+    if lower.contains("error") || lower.contains("traceback") || lower.contains("exception") {
+        line.red().to_string()
+    } else if lower.contains("transaction") {
+        line.green().to_string()
+    } else if lower.contains("block") {
+        line.cyan().to_string()
+    } else {
+        line.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colorize_highlights_errors_transactions_and_blocks() {
+        let error = "Traceback (most recent call last):";
+        assert_eq!(colorize(error), error.red().to_string());
+        let transaction = "Transaction 0x1 accepted";
+        assert_eq!(colorize(transaction), transaction.green().to_string());
+        let block = "Block 2 created";
+        assert_eq!(colorize(block), block.cyan().to_string());
+        assert_eq!(colorize("Account #0"), "Account #0");
+    }
+}
@@ -1,21 +1,53 @@
+mod admin;
+mod apply;
+mod claim;
+mod cluster;
+mod crd;
+mod describe;
 mod devnet;
+mod jsonpath;
+mod local;
+mod logs;
+mod output;
+mod pod;
+mod port_forward;
+mod template;
+mod wait;
 
-use anyhow::Result;
-use clap::{Parser, Subcommand};
-use devnet::DevnetOut;
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Result};
+use apply::ApplyOptions;
+use chrono::Utc;
+use clap::{Args, Parser, Subcommand};
+use cluster::{Cluster, ClusterArgs};
+use local::LocalDevnets;
+use logs::LogsOptions;
+use output::OutputFormat;
 use ryogoku_operator::{
-    k8s_openapi::apiextensions_apiserver::pkg::apis::apiextensions::v1::CustomResourceDefinition,
+    k8s_openapi::{
+        api::core::v1::Pod,
+        apiextensions_apiserver::pkg::apis::apiextensions::v1::CustomResourceDefinition,
+    },
     kube::{
-        api::{Api, DeleteParams, ListParams, ObjectMeta, PostParams},
-        Client, CustomResourceExt, ResourceExt,
+        api::{Api, DeleteParams, ListParams, ObjectMeta, Patch, PatchParams, PostParams},
+        ResourceExt,
     },
-    Devnet, DevnetSpec,
+    Devnet, DevnetClaim, DevnetClient, DevnetCloneSource, DevnetExposure, DevnetSpec,
+    DevnetStorage, DevnetTemplateKind, DevnetTemplateRef, ExposureMode, ExposureTls,
+    RESTART_ANNOTATION,
 };
-use tabled::{Style, Table};
+use serde_json::json;
+use wait::WaitFor;
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 struct RyogokuCli {
+    #[command(flatten)]
+    cluster: ClusterArgs,
+    /// Run development networks as local processes instead of in the cluster.
+    #[arg(long, global = true, env = "RYOGOKU_LOCAL")]
+    local: bool,
     #[command(subcommand)]
     command: RyogokuCommand,
 }
@@ -30,7 +62,81 @@ enum RyogokuCommand {
     /// Manage development networks.
     Devnet {
         #[command(subcommand)]
-        command: DevnetCommand,
+        command: Box<DevnetCommand>,
+    },
+    /// Manage development network templates.
+    Template {
+        #[command(subcommand)]
+        command: TemplateCommand,
+    },
+    /// Server-side apply the Ryogoku resources of files or directories.
+    Apply {
+        /// File or directory of YAML or JSON manifests, or `-` for stdin.
+        #[arg(short, long, required = true)]
+        filename: Vec<PathBuf>,
+        /// Delete the resources matching the selector, applied by this command, that are not in
+        /// the files.
+        #[arg(long, requires = "selector")]
+        prune: bool,
+        /// Label selector of the resources to prune, like `env=qa`.
+        #[arg(short = 'l', long)]
+        selector: Option<String>,
+        /// Print the changes instead of applying them.
+        #[arg(long)]
+        diff: bool,
+        /// Submit request but don't persist it
+        #[arg(long)]
+        dry_run: bool,
+        /// Take ownership of the fields managed by other field managers.
+        #[arg(long)]
+        force_conflicts: bool,
+    },
+    /// Delete the Ryogoku resources of files or directories.
+    Delete {
+        /// File or directory of YAML or JSON manifests, or `-` for stdin.
+        #[arg(short, long, required = true)]
+        filename: Vec<PathBuf>,
+        /// Submit request but don't persist it
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum TimeCommand {
+    /// Set the time of the next block.
+    Set {
+        /// Network name.
+        name: String,
+        /// Unix time in seconds.
+        time: u64,
+    },
+    /// Move the time forward.
+    Increase {
+        /// Network name.
+        name: String,
+        /// Duration to add, like `1h` or `30s`.
+        #[arg(value_parser = humantime::parse_duration)]
+        duration: std::time::Duration,
+    },
+}
+
+#[derive(Subcommand)]
+enum PostmanCommand {
+    /// Deliver the pending messages between L1 and L2.
+    Flush {
+        /// Network name.
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum TemplateCommand {
+    /// List namespace and cluster templates.
+    List {
+        /// If present, list namespace templates in all namespaces.
+        #[arg(short = 'A', long)]
+        all_namespaces: bool,
     },
 }
 
@@ -44,6 +150,61 @@ enum CrdCommand {
         #[arg(short)]
         dry_run: bool,
     },
+    /// Compare the CRD installed in cluster with this version
+    Diff,
+    /// Upgrade the CRD installed in cluster to this version
+    Upgrade {
+        /// Submit request but don't persist it
+        #[arg(short)]
+        dry_run: bool,
+    },
+    /// Remove CRD from cluster
+    Uninstall {
+        /// Uninstall even if devnets exist, deleting them
+        #[arg(long)]
+        force: bool,
+        /// Submit request but don't persist it
+        #[arg(short)]
+        dry_run: bool,
+    },
+}
+
+/// Devnet arguments of the `create` command, supported in cluster and local modes.
+#[derive(Args)]
+struct SpecArgs {
+    /// Number of pre-deployed accounts.
+    #[arg(long)]
+    accounts: Option<usize>,
+    /// Initial balance of the pre-deployed accounts, in wei.
+    #[arg(long)]
+    initial_balance: Option<String>,
+    /// Randomness seed of the pre-deployed accounts.
+    #[arg(long)]
+    seed: Option<String>,
+    /// Start time of the genesis block, in Unix time seconds.
+    #[arg(long)]
+    start_time: Option<u64>,
+    /// Gas price, in wei.
+    #[arg(long)]
+    gas_price: Option<String>,
+    /// Apply all the lite mode optimizations.
+    #[arg(long)]
+    lite_mode: bool,
+}
+
+impl SpecArgs {
+    /// Returns the devnet spec with the arguments set.
+    fn spec(self) -> DevnetSpec {
+        DevnetSpec {
+            accounts: self.accounts,
+            initial_balance: self.initial_balance,
+            seed: self.seed,
+            start_time: self.start_time,
+            gas_price: self.gas_price,
+            lite_mode: self.lite_mode.then_some(true),
+            ..DevnetSpec::default()
+        }
+    }
 }
 
 #[derive(Subcommand)]
@@ -52,97 +213,304 @@ enum DevnetCommand {
     Create {
         /// Network name.
         name: String,
-        /// Create the network in the given namespace.
-        #[arg(short, long)]
-        namespace: Option<String>,
+        /// Use the fields of the given DevnetTemplate, in the network namespace.
+        #[arg(long)]
+        template: Option<String>,
+        /// Use the fields of the given ClusterDevnetTemplate.
+        #[arg(long, conflicts_with = "template")]
+        cluster_template: Option<String>,
         /// Specify the service type.
         #[arg(short, long)]
         service_type: Option<String>,
         /// Shorthand for `--service-type=LoadBalancer`.
         #[arg(short, long)]
         expose: bool,
+        /// Expose the network through an Ingress with the given host template,
+        /// for example `{name}.{namespace}.devnets.example.com`.
+        #[arg(long)]
+        host: Option<String>,
+        /// Expose the network through an HTTPRoute attached to the given Gateway instead of an Ingress.
+        #[arg(long, requires = "host")]
+        gateway: Option<String>,
+        /// Serve the exposed network over TLS.
+        #[arg(long, requires = "host")]
+        tls: bool,
+        /// Delete the network after this duration, like `2h`.
+        #[arg(long, value_parser = humantime::parse_duration)]
+        ttl: Option<std::time::Duration>,
+        /// Delete the network when it served no request and produced no block for this duration,
+        /// like `30m`.
+        #[arg(long, value_parser = humantime::parse_duration)]
+        idle_ttl: Option<std::time::Duration>,
+        /// Store the network state on a volume of the given size, like `1Gi`. Required to pause it.
+        #[arg(long)]
+        storage: Option<String>,
+        #[command(flatten)]
+        spec_args: Box<SpecArgs>,
+    },
+    /// Create a development network with the spec and the current state of another one.
+    Clone {
+        /// Source network name.
+        source: String,
+        /// New network name.
+        name: String,
+        /// Namespace of the new network. Defaults to the source namespace.
+        #[arg(long)]
+        target_namespace: Option<String>,
     },
     /// List all development networks.
     List {
-        /// List networks in the given namespace.
-        #[arg(short, long)]
-        namespace: Option<String>,
         /// If present, list networks in all namespaces.
         #[arg(short = 'A', long)]
         all_namespaces: bool,
+        /// Output format: table, wide, json, yaml, name or jsonpath=<template>.
+        #[arg(short, long, default_value = "table")]
+        output: OutputFormat,
+        /// Label selector to filter on, like `key=value`.
+        #[arg(short = 'l', long)]
+        selector: Option<String>,
+        /// Field selector to filter on, like `metadata.name=my-devnet`.
+        #[arg(long)]
+        field_selector: Option<String>,
+        /// Watch for changes and print the networks again.
+        #[arg(short, long)]
+        watch: bool,
+    },
+    /// Display one development network.
+    Get {
+        /// Network name.
+        name: String,
+        /// Output format: table, wide, json, yaml, name or jsonpath=<template>.
+        #[arg(short, long, default_value = "table")]
+        output: OutputFormat,
+    },
+    /// Show details of a development network, its pod, service and events.
+    Describe {
+        /// Network name.
+        name: String,
+    },
+    /// Wait for a development network to reach a state.
+    Wait {
+        /// Network name.
+        name: String,
+        /// State to wait for.
+        #[arg(long = "for", value_enum, default_value = "ready")]
+        wait_for: WaitFor,
+        /// Give up after this duration, like `30s` or `2m`.
+        #[arg(long, default_value = "120s", value_parser = humantime::parse_duration)]
+        timeout: std::time::Duration,
+    },
+    /// Dump the state of a development network and stop it.
+    Pause {
+        /// Network name.
+        name: String,
+    },
+    /// Restart a paused development network from its state.
+    Resume {
+        /// Network name.
+        name: String,
+    },
+    /// Claim a ready development network from a pool and print its name.
+    Claim {
+        /// Pool name.
+        pool: String,
+        /// Claim name, generated from the pool name if missing.
+        #[arg(long)]
+        name: Option<String>,
+        /// Give up after this duration, like `30s` or `2m`, deleting the claim.
+        #[arg(long, default_value = "120s", value_parser = humantime::parse_duration)]
+        timeout: std::time::Duration,
+    },
+    /// Release a claimed development network, deleting it.
+    Release {
+        /// Claim name.
+        claim: String,
+    },
+    /// Mint tokens to an address.
+    Fund {
+        /// Network name.
+        name: String,
+        /// Address to fund.
+        address: String,
+        /// Amount to mint, in the smallest unit.
+        amount: u128,
+        /// Token to mint.
+        #[arg(long, value_enum)]
+        unit: Option<admin::Unit>,
+    },
+    /// Create blocks, including the pending transactions.
+    Mine {
+        /// Network name.
+        name: String,
+        /// Number of blocks to create.
+        #[arg(long, default_value_t = 1)]
+        blocks: u32,
+    },
+    /// Control the time of a development network.
+    Time {
+        #[command(subcommand)]
+        command: TimeCommand,
+    },
+    /// Abort blocks, reverting their transactions.
+    AbortBlocks {
+        /// Network name.
+        name: String,
+        /// Hash of the first block to abort. All the blocks after it are aborted too.
+        #[arg(long)]
+        from: String,
+    },
+    /// Manage L1-L2 messaging of a development network.
+    Postman {
+        #[command(subcommand)]
+        command: PostmanCommand,
+    },
+    /// Recreate the pod of a development network from genesis, keeping its service.
+    Restart {
+        /// Network name.
+        name: String,
+    },
+    /// Reset a development network to genesis without restarting it.
+    Reset {
+        /// Network name.
+        name: String,
     },
     /// Delete a development network.
     Delete {
         /// Network name.
         name: String,
-        /// Delete the network in the given namespace.
+    },
+    /// Forward the network rpc and gateway ports to localhost.
+    PortForward {
+        /// Network name.
+        name: String,
+        /// Local port for the rpc endpoint.
+        #[arg(long, default_value_t = 9575)]
+        rpc_port: u16,
+        /// Local port for the gateway endpoint.
+        #[arg(long, default_value_t = 5050)]
+        gateway_port: u16,
+    },
+    /// Print the logs of a development network.
+    Logs {
+        /// Network name.
+        name: String,
+        /// Stream new logs.
+        #[arg(short, long)]
+        follow: bool,
+        /// Only print logs newer than a relative duration, like `30s` or `10m`.
+        #[arg(long, value_parser = humantime::parse_duration)]
+        since: Option<std::time::Duration>,
+        /// Print the logs of the previous container instance.
         #[arg(short, long)]
-        namespace: Option<String>,
+        previous: bool,
+        /// Number of recent lines to print.
+        #[arg(long)]
+        tail: Option<i64>,
+        /// Don't highlight transactions and blocks.
+        #[arg(long)]
+        no_color: bool,
     },
 }
 
-async fn crd(command: CrdCommand) -> Result<()> {
+async fn crd(command: CrdCommand, cluster: ClusterArgs) -> Result<()> {
+    let desired = ryogoku_operator::crds();
     match command {
         CrdCommand::Print => {
-            let crd = serde_yaml::to_string(&Devnet::crd())?;
-            println!("{}", crd);
+            let docs = desired
+                .iter()
+                .map(serde_yaml::to_string)
+                .collect::<Result<Vec<_>, _>>()?;
+            println!("{}", docs.join("---\n"));
             Ok(())
         }
         CrdCommand::Install { dry_run } => {
-            let client = Client::try_default().await?;
+            let Cluster { client, .. } = cluster.connect().await?;
             let crds: Api<CustomResourceDefinition> = Api::all(client);
-            let crd_name = Devnet::crd_name();
-            let existing = crds.get_opt(crd_name).await?;
-
-            if existing.is_some() {
-                println!("CRD {} already exists.", crd_name);
-                println!();
-                println!("Nothing to do, bye! 🕹");
-                return Ok(());
+            for crd in &desired {
+                crd::install(&crds, crd, dry_run).await?;
             }
+            println!();
+            println!("Thanks for using Ryogoku 🕹");
+            Ok(())
+        }
+        CrdCommand::Diff => {
+            let Cluster { client, .. } = cluster.connect().await?;
+            let crds: Api<CustomResourceDefinition> = Api::all(client);
+            for crd in &desired {
+                let crd_name = crd.name_any();
+                let existing = crds
+                    .get_opt(&crd_name)
+                    .await?
+                    .ok_or_else(|| anyhow!("CRD {} is not installed", crd_name))?;
 
-            let opts = PostParams {
-                dry_run,
-                ..Default::default()
-            };
-
-            println!("Creating CRD {}...", crd_name);
-            match crds.create(&opts, &Devnet::crd()).await {
-                Ok(_) => {
-                    println!(" 📦 CRD installed.");
-                    println!();
-                    println!("Thanks for using Ryogoku 🕹");
-                    Ok(())
-                }
-                Err(err) => {
-                    println!(" 🩹 Something went wrong:");
-                    println!("Error: {}", err);
-                    Ok(())
+                match crd::diff(&existing, crd)? {
+                    Some(diff) => print!("{}", diff),
+                    None => println!("CRD {} is up to date.", crd_name),
                 }
             }
+            Ok(())
+        }
+        CrdCommand::Upgrade { dry_run } => {
+            let Cluster { client, .. } = cluster.connect().await?;
+            let crds: Api<CustomResourceDefinition> = Api::all(client);
+            for crd in &desired {
+                crd::upgrade(&crds, crd, dry_run).await?;
+            }
+            Ok(())
+        }
+        CrdCommand::Uninstall { force, dry_run } => {
+            let Cluster { client, .. } = cluster.connect().await?;
+            let crds: Api<CustomResourceDefinition> = Api::all(client.clone());
+            let devnets: Api<Devnet> = Api::all(client);
+            crd::uninstall(&crds, &devnets, &desired, force, dry_run).await
         }
     }
 }
 
-async fn devnet(command: DevnetCommand) -> Result<()> {
-    let client = Client::try_default().await?;
+async fn devnet(command: DevnetCommand, cluster: ClusterArgs) -> Result<()> {
+    let Cluster { client, namespace } = cluster.connect().await?;
     match command {
         DevnetCommand::Create {
             name,
-            namespace,
+            template,
+            cluster_template,
             service_type,
             expose,
+            host,
+            gateway,
+            tls,
+            ttl,
+            idle_ttl,
+            storage,
+            spec_args,
         } => {
-            let namespace = namespace.unwrap_or_else(|| "default".to_string());
             let devnets: Api<Devnet> = Api::namespaced(client, &namespace);
 
             let service_type = if service_type.is_some() {
                 service_type
             } else if expose {
-                Some("NodePort".to_string())
+                Some("LoadBalancer".to_string())
             } else {
                 None
             };
+            let exposure = host.map(|host| DevnetExposure {
+                mode: gateway.as_ref().map(|_| ExposureMode::HttpRoute),
+                host,
+                gateway_name: gateway,
+                tls: tls.then(ExposureTls::default),
+                ..DevnetExposure::default()
+            });
+            let template_ref = match (template, cluster_template) {
+                (Some(name), _) => Some(DevnetTemplateRef {
+                    name,
+                    kind: Some(DevnetTemplateKind::DevnetTemplate),
+                }),
+                (None, Some(name)) => Some(DevnetTemplateRef {
+                    name,
+                    kind: Some(DevnetTemplateKind::ClusterDevnetTemplate),
+                }),
+                (None, None) => None,
+            };
             let data = Devnet {
                 metadata: ObjectMeta {
                     name: Some(name),
@@ -150,8 +518,16 @@ async fn devnet(command: DevnetCommand) -> Result<()> {
                     ..ObjectMeta::default()
                 },
                 spec: DevnetSpec {
+                    template_ref,
                     service_type,
-                    ..DevnetSpec::default()
+                    exposure,
+                    ttl_seconds_after_creation: ttl.map(|ttl| ttl.as_secs()),
+                    ttl_seconds_after_idle: idle_ttl.map(|ttl| ttl.as_secs()),
+                    storage: storage.map(|size| DevnetStorage {
+                        size: Some(size),
+                        ..DevnetStorage::default()
+                    }),
+                    ..spec_args.spec()
                 },
                 status: None,
             };
@@ -161,28 +537,240 @@ async fn devnet(command: DevnetCommand) -> Result<()> {
 
             Ok(())
         }
+        DevnetCommand::Clone {
+            source,
+            name,
+            target_namespace,
+        } => {
+            let devnets: Api<Devnet> = Api::namespaced(client.clone(), &namespace);
+            let source_devnet = devnets.get(&source).await?;
+
+            // the template may not exist in the target namespace, copy its fields instead.
+            let source_devnet = template::merge_templates(client.clone(), vec![source_devnet])
+                .await?
+                .remove(0);
+
+            // the source state already has the results of its bootstrap steps, dojo
+            // migration and startup settings.
+            let mut spec = source_devnet.spec;
+            spec.template_ref = None;
+            spec.bootstrap = None;
+            spec.dojo = None;
+            spec.funded_addresses = None;
+            if let Some(block_production) = spec.block_production.as_mut() {
+                block_production.time = None;
+            }
+
+            let target_namespace = target_namespace.unwrap_or_else(|| namespace.clone());
+            let data = Devnet {
+                metadata: ObjectMeta {
+                    name: Some(name),
+                    namespace: Some(target_namespace.clone()),
+                    ..ObjectMeta::default()
+                },
+                spec: DevnetSpec {
+                    paused: None,
+                    clone_from: Some(DevnetCloneSource {
+                        name: source.clone(),
+                        namespace: Some(namespace),
+                    }),
+                    ..spec
+                },
+                status: None,
+            };
+            let targets: Api<Devnet> = Api::namespaced(client, &target_namespace);
+            let devnet = targets.create(&PostParams::default(), &data).await?;
+
+            println!("devnet {} created from {}", devnet.name_any(), source);
+
+            Ok(())
+        }
         DevnetCommand::List {
-            namespace,
             all_namespaces,
+            output,
+            selector,
+            field_selector,
+            watch,
         } => {
-            let devnets: Api<Devnet> = if all_namespaces {
-                Api::all(client)
+            let (devnets, pods): (Api<Devnet>, Api<Pod>) = if all_namespaces {
+                (Api::all(client.clone()), Api::all(client.clone()))
             } else {
-                let namespace = namespace.unwrap_or_else(|| "default".to_string());
-                Api::namespaced(client, &namespace)
+                (
+                    Api::namespaced(client.clone(), &namespace),
+                    Api::namespaced(client.clone(), &namespace),
+                )
             };
 
-            let all = devnets.list(&ListParams::default()).await?;
+            let mut lp = ListParams::default();
+            if let Some(selector) = &selector {
+                lp = lp.labels(selector);
+            }
+            if let Some(field_selector) = &field_selector {
+                lp = lp.fields(field_selector);
+            }
+
+            if watch {
+                return output::watch_devnets(client, devnets, &pods, lp, &output).await;
+            }
 
-            let all_out: Vec<_> = all.into_iter().map(DevnetOut::new).collect();
+            let mut all = devnets.list(&lp).await?.items;
+            // tables show the fields coming from templates, structured formats the object.
+            if output.is_table() {
+                all = template::merge_templates(client, all).await?;
+            }
+            output::print_devnets(all, Some(&pods), &output, true).await
+        }
+        DevnetCommand::Get { name, output } => {
+            let devnets: Api<Devnet> = Api::namespaced(client.clone(), &namespace);
+            let pods: Api<Pod> = Api::namespaced(client.clone(), &namespace);
 
-            let table = Table::new(all_out).with(Style::empty()).to_string();
-            println!("{}", table);
+            let mut devnets = vec![devnets.get(&name).await?];
+            if output.is_table() {
+                devnets = template::merge_templates(client, devnets).await?;
+            }
+            output::print_devnets(devnets, Some(&pods), &output, false).await
+        }
+        DevnetCommand::Describe { name } => describe::describe(client, &namespace, &name).await,
+        DevnetCommand::Wait {
+            name,
+            wait_for,
+            timeout,
+        } => {
+            let devnets: Api<Devnet> = Api::namespaced(client, &namespace);
+
+            wait::wait(devnets, &name, wait_for, timeout).await
+        }
+        DevnetCommand::Pause { name } => {
+            let devnets: Api<Devnet> = Api::namespaced(client, &namespace);
+
+            // templates may provide the storage, the operator reports it if they don't.
+            let devnet = devnets.get(&name).await?;
+            if devnet.spec.storage.is_none() && devnet.spec.template_ref.is_none() {
+                return Err(anyhow!(
+                    "devnet {} has no storage, only devnets created with --storage can be paused",
+                    name
+                ));
+            }
+
+            let patch = json!({ "spec": { "paused": true } });
+            devnets
+                .patch(&name, &PatchParams::default(), &Patch::Merge(&patch))
+                .await?;
+
+            println!("devnet {} paused", name);
 
             Ok(())
         }
-        DevnetCommand::Delete { name, namespace } => {
-            let namespace = namespace.unwrap_or_else(|| "default".to_string());
+        DevnetCommand::Resume { name } => {
+            let devnets: Api<Devnet> = Api::namespaced(client, &namespace);
+
+            let patch = json!({ "spec": { "paused": false } });
+            devnets
+                .patch(&name, &PatchParams::default(), &Patch::Merge(&patch))
+                .await?;
+
+            println!("devnet {} resumed", name);
+
+            Ok(())
+        }
+        DevnetCommand::Claim {
+            pool,
+            name,
+            timeout,
+        } => {
+            let claims: Api<DevnetClaim> = Api::namespaced(client, &namespace);
+
+            claim::claim(claims, &namespace, &pool, name, timeout).await
+        }
+        DevnetCommand::Release { claim } => {
+            let claims: Api<DevnetClaim> = Api::namespaced(client, &namespace);
+
+            let dp = DeleteParams::default();
+            let _claim = claims.delete(&claim, &dp).await?;
+
+            println!("claim {} released", claim);
+
+            Ok(())
+        }
+        DevnetCommand::Fund {
+            name,
+            address,
+            amount,
+            unit,
+        } => {
+            let devnet = DevnetClient::new(client, &namespace, &name);
+
+            admin::fund(&devnet, &address, amount, unit).await
+        }
+        DevnetCommand::Mine { name, blocks } => {
+            let devnet = DevnetClient::new(client, &namespace, &name);
+
+            admin::mine(&devnet, &name, blocks).await
+        }
+        DevnetCommand::Time {
+            command: TimeCommand::Set { name, time },
+        } => {
+            let devnet = DevnetClient::new(client, &namespace, &name);
+
+            admin::set_time(&devnet, &name, time).await
+        }
+        DevnetCommand::Time {
+            command: TimeCommand::Increase { name, duration },
+        } => {
+            let devnet = DevnetClient::new(client, &namespace, &name);
+
+            admin::increase_time(&devnet, &name, duration).await
+        }
+        DevnetCommand::AbortBlocks { name, from } => {
+            let devnet = DevnetClient::new(client, &namespace, &name);
+
+            admin::abort_blocks(&devnet, &name, &from).await
+        }
+        DevnetCommand::Postman {
+            command: PostmanCommand::Flush { name },
+        } => {
+            let devnet = DevnetClient::new(client, &namespace, &name);
+
+            let response: serde_json::Value =
+                devnet.post("gateway", "postman/flush", &json!({})).await?;
+            let consumed = |direction: &str| {
+                response["consumed_messages"][direction]
+                    .as_array()
+                    .map(|messages| messages.len())
+                    .unwrap_or(0)
+            };
+
+            println!(
+                "devnet {} flushed {} messages from L1 and {} messages from L2",
+                name,
+                consumed("from_l1"),
+                consumed("from_l2")
+            );
+
+            Ok(())
+        }
+        DevnetCommand::Restart { name } => {
+            let devnets: Api<Devnet> = Api::namespaced(client, &namespace);
+
+            let patch = json!({
+                "metadata": {
+                    "annotations": { RESTART_ANNOTATION: Utc::now().to_rfc3339() }
+                }
+            });
+            devnets
+                .patch(&name, &PatchParams::default(), &Patch::Merge(&patch))
+                .await?;
+
+            println!("devnet {} restarting", name);
+
+            Ok(())
+        }
+        DevnetCommand::Reset { name } => {
+            let devnet = DevnetClient::new(client, &namespace, &name);
+
+            admin::reset(&devnet, &name).await
+        }
+        DevnetCommand::Delete { name } => {
             let devnets: Api<Devnet> = Api::namespaced(client, &namespace);
 
             let dp = DeleteParams::default();
@@ -192,6 +780,134 @@ async fn devnet(command: DevnetCommand) -> Result<()> {
 
             Ok(())
         }
+        DevnetCommand::PortForward {
+            name,
+            rpc_port,
+            gateway_port,
+        } => {
+            let pods: Api<Pod> = Api::namespaced(client, &namespace);
+
+            port_forward::port_forward(pods, &name, rpc_port, gateway_port).await
+        }
+        DevnetCommand::Logs {
+            name,
+            follow,
+            since,
+            previous,
+            tail,
+            no_color,
+        } => {
+            let pods: Api<Pod> = Api::namespaced(client, &namespace);
+
+            let opts = LogsOptions {
+                follow,
+                since,
+                previous,
+                tail,
+                color: !no_color,
+            };
+            logs::logs(pods, &name, opts).await
+        }
+    }
+}
+
+/// Run the devnet commands supported in local mode against local processes.
+async fn local_devnet(command: DevnetCommand) -> Result<()> {
+    let devnets = LocalDevnets::open()?;
+    match command {
+        DevnetCommand::Create {
+            name,
+            template,
+            cluster_template,
+            service_type,
+            expose,
+            host,
+            gateway,
+            tls,
+            ttl,
+            idle_ttl,
+            storage,
+            spec_args,
+        } => {
+            if template.is_some() || cluster_template.is_some() {
+                return Err(anyhow!("templates are not supported in local mode"));
+            }
+            let unsupported = [
+                ("--ttl", ttl.is_some()),
+                ("--idle-ttl", idle_ttl.is_some()),
+                ("--storage", storage.is_some()),
+                ("--host", host.is_some()),
+                ("--gateway", gateway.is_some()),
+                ("--tls", tls),
+                ("--service-type", service_type.is_some()),
+                ("--expose", expose),
+            ];
+            if let Some((flag, _)) = unsupported.iter().find(|(_, set)| *set) {
+                return Err(anyhow!("{} is not supported in local mode", flag));
+            }
+
+            let devnet = devnets.create(&name, spec_args.spec())?;
+
+            println!("devnet {} created", devnet.name_any());
+
+            Ok(())
+        }
+        DevnetCommand::List {
+            all_namespaces,
+            output,
+            selector,
+            field_selector,
+            watch: false,
+        } => {
+            let unsupported = [
+                ("--all-namespaces", all_namespaces),
+                ("--selector", selector.is_some()),
+                ("--field-selector", field_selector.is_some()),
+            ];
+            if let Some((flag, _)) = unsupported.iter().find(|(_, set)| *set) {
+                return Err(anyhow!("{} is not supported in local mode", flag));
+            }
+
+            output::print_devnets(devnets.list()?, None, &output, true).await
+        }
+        DevnetCommand::Get { name, output } => {
+            output::print_devnets(vec![devnets.get(&name)?], None, &output, false).await
+        }
+        DevnetCommand::Delete { name } => {
+            devnets.delete(&name)?;
+
+            println!("devnet {} deleted", name);
+
+            Ok(())
+        }
+        DevnetCommand::Logs {
+            name,
+            follow,
+            since,
+            previous,
+            tail,
+            no_color,
+        } => {
+            let opts = LogsOptions {
+                follow,
+                since,
+                previous,
+                tail,
+                color: !no_color,
+            };
+            devnets.logs(&name, opts).await
+        }
+        _ => Err(anyhow!("command not supported in local mode")),
+    }
+}
+
+async fn template(command: TemplateCommand, cluster: ClusterArgs) -> Result<()> {
+    let Cluster { client, namespace } = cluster.connect().await?;
+    match command {
+        TemplateCommand::List { all_namespaces } => {
+            let namespace = (!all_namespaces).then_some(namespace.as_str());
+            template::list(client, namespace).await
+        }
     }
 }
 
@@ -199,8 +915,40 @@ async fn devnet(command: DevnetCommand) -> Result<()> {
 async fn main() -> Result<()> {
     let cli = RyogokuCli::parse();
 
+    if cli.local {
+        return match cli.command {
+            RyogokuCommand::Devnet { command } => local_devnet(*command).await,
+            _ => Err(anyhow!("command not supported in local mode")),
+        };
+    }
+
     match cli.command {
-        RyogokuCommand::Crd { command } => crd(command).await,
-        RyogokuCommand::Devnet { command } => devnet(command).await,
+        RyogokuCommand::Crd { command } => crd(command, cli.cluster).await,
+        RyogokuCommand::Devnet { command } => devnet(*command, cli.cluster).await,
+        RyogokuCommand::Template { command } => template(command, cli.cluster).await,
+        RyogokuCommand::Apply {
+            filename,
+            prune,
+            selector,
+            diff,
+            dry_run,
+            force_conflicts,
+        } => {
+            let manifests = apply::read_manifests(&filename)?;
+            let Cluster { client, namespace } = cli.cluster.connect().await?;
+            let opts = ApplyOptions {
+                prune,
+                selector,
+                diff,
+                dry_run,
+                force_conflicts,
+            };
+            apply::apply(client, &namespace, &manifests, opts).await
+        }
+        RyogokuCommand::Delete { filename, dry_run } => {
+            let manifests = apply::read_manifests(&filename)?;
+            let Cluster { client, namespace } = cli.cluster.connect().await?;
+            apply::delete(client, &namespace, &manifests, dry_run).await
+        }
     }
 }
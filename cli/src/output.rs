@@ -0,0 +1,212 @@
+use std::{collections::BTreeMap, io::IsTerminal, str::FromStr};
+
+use anyhow::{anyhow, Result};
+use futures::{StreamExt, TryStreamExt};
+use ryogoku_operator::{
+    k8s_openapi::api::core::v1::Pod,
+    kube::{
+        api::{Api, ListParams},
+        runtime::watcher,
+        Client, ResourceExt,
+    },
+    Devnet,
+};
+use serde_json::json;
+use tabled::{Style, Table};
+
+use crate::{
+    devnet::{DevnetOut, DevnetWideOut},
+    jsonpath::Template,
+    template::merge_templates,
+};
+
+/// Output format of the commands printing devnets.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Human readable table.
+    #[default]
+    Table,
+    /// Table with additional columns.
+    Wide,
+    /// JSON object, or a JSON list when printing multiple devnets.
+    Json,
+    /// YAML object, or a YAML list when printing multiple devnets.
+    Yaml,
+    /// Resource names only, one per line.
+    Name,
+    /// JSONPath template applied to the object, or list.
+    JsonPath(Template),
+}
+
+impl OutputFormat {
+    /// Returns true if the format prints a table.
+    pub fn is_table(&self) -> bool {
+        matches!(self, OutputFormat::Table | OutputFormat::Wide)
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if let Some(template) = s.strip_prefix("jsonpath=") {
+            return Ok(OutputFormat::JsonPath(Template::parse(template)?));
+        }
+
+        match s {
+            "table" => Ok(OutputFormat::Table),
+            "wide" => Ok(OutputFormat::Wide),
+            "json" => Ok(OutputFormat::Json),
+            "yaml" => Ok(OutputFormat::Yaml),
+            "name" => Ok(OutputFormat::Name),
+            _ => Err(anyhow!(
+                "unknown output format {}, expected one of table, wide, json, yaml, name, jsonpath=<template>",
+                s
+            )),
+        }
+    }
+}
+
+/// Print the devnets in the given format.
+///
+/// Set `list` to print structured formats as a `List`, even with a single devnet.
+/// `pods` is only used by the wide format, to look up the nodes running the devnets. Local
+/// devnets have no pods.
+pub async fn print_devnets(
+    devnets: Vec<Devnet>,
+    pods: Option<&Api<Pod>>,
+    format: &OutputFormat,
+    list: bool,
+) -> Result<()> {
+    match format {
+        OutputFormat::Table => {
+            let out: Vec<_> = devnets.into_iter().map(DevnetOut::new).collect();
+            let table = Table::new(out).with(Style::empty()).to_string();
+            println!("{}", table);
+        }
+        OutputFormat::Wide => {
+            let nodes = match pods {
+                Some(pods) => devnet_nodes(pods).await?,
+                None => BTreeMap::default(),
+            };
+            let out: Vec<_> = devnets
+                .into_iter()
+                .map(|devnet| {
+                    let key = (devnet.namespace().unwrap_or_default(), devnet.name_any());
+                    let node = nodes.get(&key).cloned();
+                    DevnetWideOut::new(devnet, node)
+                })
+                .collect();
+            let table = Table::new(out).with(Style::empty()).to_string();
+            println!("{}", table);
+        }
+        OutputFormat::Json => {
+            let value = structured(devnets, list)?;
+            println!("{}", serde_json::to_string_pretty(&value)?);
+        }
+        OutputFormat::Yaml => {
+            let value = structured(devnets, list)?;
+            print!("{}", serde_yaml::to_string(&value)?);
+        }
+        OutputFormat::Name => {
+            for devnet in devnets {
+                println!("devnet.ryogoku.stark/{}", devnet.name_any());
+            }
+        }
+        OutputFormat::JsonPath(template) => {
+            let value = structured(devnets, list)?;
+            println!("{}", template.render(&value));
+        }
+    }
+
+    Ok(())
+}
+
+/// Print the devnets matching the list params, then print them again every time they change.
+pub async fn watch_devnets(
+    client: Client,
+    devnets: Api<Devnet>,
+    pods: &Api<Pod>,
+    lp: ListParams,
+    format: &OutputFormat,
+) -> Result<()> {
+    let redraw = format.is_table() && std::io::stdout().is_terminal();
+    let mut events = watcher(devnets.clone(), lp.clone()).boxed();
+
+    while events.try_next().await?.is_some() {
+        let mut all = devnets.list(&lp).await?.items;
+        if format.is_table() {
+            all = merge_templates(client.clone(), all).await?;
+        }
+        if redraw {
+            // clear screen and move cursor to the top left corner.
+            print!("\x1b[2J\x1b[H");
+        }
+        print_devnets(all, Some(pods), format, true).await?;
+    }
+
+    Ok(())
+}
+
+fn structured(mut devnets: Vec<Devnet>, list: bool) -> Result<serde_json::Value> {
+    if !list && devnets.len() == 1 {
+        let devnet = devnets.remove(0);
+        return Ok(serde_json::to_value(devnet)?);
+    }
+
+    Ok(json!({
+        "apiVersion": "v1",
+        "kind": "List",
+        "items": devnets,
+    }))
+}
+
+/// Returns the node running each devnet, keyed by devnet namespace and name.
+async fn devnet_nodes(pods: &Api<Pod>) -> Result<BTreeMap<(String, String), String>> {
+    let lp = ListParams::default().labels("ryogoku.stark/devnet_name");
+    let nodes = pods
+        .list(&lp)
+        .await?
+        .into_iter()
+        .filter_map(|pod| {
+            let devnet = pod.labels().get("ryogoku.stark/devnet_name")?.clone();
+            let node = pod.spec.as_ref()?.node_name.clone()?;
+            Some(((pod.namespace().unwrap_or_default(), devnet), node))
+        })
+        .collect();
+    Ok(nodes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_output_formats() {
+        assert_eq!(
+            "table".parse::<OutputFormat>().unwrap(),
+            OutputFormat::Table
+        );
+        assert_eq!("wide".parse::<OutputFormat>().unwrap(), OutputFormat::Wide);
+        assert_eq!("json".parse::<OutputFormat>().unwrap(), OutputFormat::Json);
+        assert_eq!("yaml".parse::<OutputFormat>().unwrap(), OutputFormat::Yaml);
+        assert_eq!("name".parse::<OutputFormat>().unwrap(), OutputFormat::Name);
+        assert_eq!(
+            "jsonpath={.metadata.name}".parse::<OutputFormat>().unwrap(),
+            OutputFormat::JsonPath(Template::parse("{.metadata.name}").unwrap())
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_output_formats() {
+        assert!("xml".parse::<OutputFormat>().is_err());
+        assert!("jsonpath={.metadata".parse::<OutputFormat>().is_err());
+    }
+
+    #[test]
+    fn only_tables_are_tables() {
+        assert!(OutputFormat::Table.is_table());
+        assert!(OutputFormat::Wide.is_table());
+        assert!(!OutputFormat::Json.is_table());
+    }
+}
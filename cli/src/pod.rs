@@ -0,0 +1,18 @@
+use anyhow::{anyhow, Result};
+use ryogoku_operator::{
+    k8s_openapi::api::core::v1::Pod,
+    kube::api::{Api, ListParams},
+};
+
+/// Find the pod of the devnet with the given name, using the `ryogoku.stark/devnet_name` label.
+///
+/// Pods that are being deleted are ignored.
+pub async fn devnet_pod(pods: &Api<Pod>, name: &str) -> Result<Pod> {
+    let lp = ListParams::default().labels(&format!("ryogoku.stark/devnet_name={}", name));
+    pods.list(&lp)
+        .await?
+        .into_iter()
+        .filter(|pod| pod.metadata.deletion_timestamp.is_none())
+        .max_by_key(|pod| pod.metadata.creation_timestamp.clone())
+        .ok_or_else(|| anyhow!("no pod found for devnet {}", name))
+}
@@ -0,0 +1,166 @@
+use anyhow::{anyhow, Context, Result};
+use ryogoku_operator::{
+    k8s_openapi::api::core::v1::Pod,
+    kube::{api::Api, ResourceExt},
+};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::pod::devnet_pod;
+
+/// Forward the devnet `rpc` and `gateway` ports to localhost.
+///
+/// The pod is looked up again for every new connection, so forwarding keeps working
+/// when the operator recreates the devnet pod.
+pub async fn port_forward(
+    pods: Api<Pod>,
+    name: &str,
+    rpc_port: u16,
+    gateway_port: u16,
+) -> Result<()> {
+    let rpc = TcpListener::bind(("127.0.0.1", rpc_port))
+        .await
+        .with_context(|| format!("failed to bind local port {}", rpc_port))?;
+    let gateway = TcpListener::bind(("127.0.0.1", gateway_port))
+        .await
+        .with_context(|| format!("failed to bind local port {}", gateway_port))?;
+
+    // check the devnet is up before printing the urls.
+    find_pod_port(&pods, name, "rpc").await?;
+
+    let (rpc_url, gateway_url) = local_urls(rpc_port, gateway_port);
+    println!("Forwarding devnet {}", name);
+    println!("  rpc:     {}", rpc_url);
+    println!("  gateway: {}", gateway_url);
+    println!();
+    println!("Press Ctrl-C to stop.");
+
+    tokio::select! {
+        res = forward(pods.clone(), name.to_string(), "rpc", rpc) => res,
+        res = forward(pods, name.to_string(), "gateway", gateway) => res,
+        _ = tokio::signal::ctrl_c() => Ok(()),
+    }
+}
+
+/// Returns the local rpc and gateway URLs of the forwarded ports.
+fn local_urls(rpc_port: u16, gateway_port: u16) -> (String, String) {
+    (
+        format!("http://127.0.0.1:{}/rpc", rpc_port),
+        format!("http://127.0.0.1:{}", gateway_port),
+    )
+}
+
+async fn forward(
+    pods: Api<Pod>,
+    name: String,
+    port_name: &'static str,
+    listener: TcpListener,
+) -> Result<()> {
+    loop {
+        let (conn, _) = listener.accept().await?;
+        let pods = pods.clone();
+        let name = name.clone();
+        tokio::spawn(async move {
+            if let Err(err) = forward_connection(&pods, &name, port_name, conn).await {
+                eprintln!("error forwarding {} connection: {:#}", port_name, err);
+            }
+        });
+    }
+}
+
+async fn forward_connection(
+    pods: &Api<Pod>,
+    name: &str,
+    port_name: &str,
+    mut conn: TcpStream,
+) -> Result<()> {
+    let (pod_name, port) = find_pod_port(pods, name, port_name).await?;
+    let mut forwarder = pods.portforward(&pod_name, &[port]).await?;
+    let mut upstream = forwarder
+        .take_stream(port)
+        .ok_or_else(|| anyhow!("port {} was not forwarded", port))?;
+    tokio::io::copy_bidirectional(&mut conn, &mut upstream).await?;
+    drop(upstream);
+    forwarder.join().await?;
+    Ok(())
+}
+
+/// Find the running devnet pod and the container port with the given name.
+async fn find_pod_port(pods: &Api<Pod>, name: &str, port_name: &str) -> Result<(String, u16)> {
+    let pod = devnet_pod(pods, name).await?;
+    Ok((pod.name_any(), pod_port(&pod, name, port_name)?))
+}
+
+/// Returns the container port with the given name, if the pod is running.
+fn pod_port(pod: &Pod, name: &str, port_name: &str) -> Result<u16> {
+    let phase = pod.status.as_ref().and_then(|s| s.phase.as_deref());
+    if phase != Some("Running") {
+        return Err(anyhow!("devnet {} pod is not running", name));
+    }
+
+    let port = pod
+        .spec
+        .iter()
+        .flat_map(|spec| spec.containers.iter())
+        .flat_map(|container| container.ports.iter().flatten())
+        .find(|port| port.name.as_deref() == Some(port_name))
+        .ok_or_else(|| anyhow!("devnet pod has no {} port", port_name))?
+        .container_port;
+
+    Ok(port.try_into()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use ryogoku_operator::k8s_openapi::api::core::v1::{
+        Container, ContainerPort, PodSpec, PodStatus,
+    };
+
+    use super::*;
+
+    fn pod(phase: &str) -> Pod {
+        let ports = [("rpc", 9575), ("gateway", 5050)]
+            .into_iter()
+            .map(|(name, port)| ContainerPort {
+                name: Some(name.to_string()),
+                container_port: port,
+                ..ContainerPort::default()
+            })
+            .collect();
+        Pod {
+            spec: Some(PodSpec {
+                containers: vec![Container {
+                    ports: Some(ports),
+                    ..Container::default()
+                }],
+                ..PodSpec::default()
+            }),
+            status: Some(PodStatus {
+                phase: Some(phase.to_string()),
+                ..PodStatus::default()
+            }),
+            ..Pod::default()
+        }
+    }
+
+    #[test]
+    fn pod_port_finds_the_named_port_of_running_pods() {
+        assert_eq!(pod_port(&pod("Running"), "my-devnet", "rpc").unwrap(), 9575);
+        assert_eq!(
+            pod_port(&pod("Running"), "my-devnet", "gateway").unwrap(),
+            5050
+        );
+        assert!(pod_port(&pod("Running"), "my-devnet", "l1").is_err());
+        assert!(pod_port(&pod("Pending"), "my-devnet", "rpc").is_err());
+    }
+
+    #[test]
+    fn local_urls_include_the_rpc_path() {
+        assert_eq!(
+            local_urls(9575, 5050),
+            (
+                "http://127.0.0.1:9575/rpc".to_string(),
+                "http://127.0.0.1:5050".to_string()
+            )
+        );
+    }
+}
@@ -0,0 +1,154 @@
+use std::{borrow::Cow, collections::BTreeMap};
+
+use anyhow::Result;
+use ryogoku_operator::{
+    k8s_openapi::apimachinery::pkg::apis::meta::v1::Time,
+    kube::{
+        api::{Api, ListParams},
+        Client, ResourceExt,
+    },
+    ClusterDevnetTemplate, Devnet, DevnetSpec, DevnetTemplate, DevnetTemplateKind,
+};
+use tabled::{Style, Table, Tabled};
+
+use crate::devnet::{time_ago, HumanReadable};
+
+/// Namespaced or cluster template, used to implement [Tabled].
+pub struct TemplateOut {
+    namespace: Option<String>,
+    name: String,
+    kind: &'static str,
+    template: DevnetSpec,
+    creation_timestamp: Option<Time>,
+}
+
+impl From<DevnetTemplate> for TemplateOut {
+    fn from(template: DevnetTemplate) -> Self {
+        TemplateOut {
+            namespace: template.namespace(),
+            name: template.name_any(),
+            kind: "DevnetTemplate",
+            creation_timestamp: template.metadata.creation_timestamp,
+            template: template.spec.template,
+        }
+    }
+}
+
+impl From<ClusterDevnetTemplate> for TemplateOut {
+    fn from(template: ClusterDevnetTemplate) -> Self {
+        TemplateOut {
+            namespace: None,
+            name: template.name_any(),
+            kind: "ClusterDevnetTemplate",
+            creation_timestamp: template.metadata.creation_timestamp,
+            template: template.spec.template,
+        }
+    }
+}
+
+impl Tabled for TemplateOut {
+    const LENGTH: usize = 5;
+
+    fn fields(&self) -> Vec<Cow<'_, str>> {
+        let namespace = self.namespace.clone().unwrap_or_else(|| "-".to_string());
+        let image = self
+            .template
+            .image
+            .clone()
+            .unwrap_or_else(|| "-".to_string());
+        let age = self
+            .creation_timestamp
+            .as_ref()
+            .map(|t| time_ago(t).to_human())
+            .unwrap_or_default();
+
+        vec![
+            Cow::Owned(namespace),
+            Cow::Borrowed(&self.name),
+            Cow::Borrowed(self.kind),
+            Cow::Owned(image),
+            Cow::Owned(age),
+        ]
+    }
+
+    fn headers() -> Vec<Cow<'static, str>> {
+        vec![
+            Cow::Owned("NAMESPACE".to_string()),
+            Cow::Owned("NAME".to_string()),
+            Cow::Owned("KIND".to_string()),
+            Cow::Owned("IMAGE".to_string()),
+            Cow::Owned("AGE".to_string()),
+        ]
+    }
+}
+
+/// Print the devnet templates of the namespace, or of all namespaces, and the cluster templates.
+pub async fn list(client: Client, namespace: Option<&str>) -> Result<()> {
+    let templates: Api<DevnetTemplate> = match namespace {
+        Some(namespace) => Api::namespaced(client.clone(), namespace),
+        None => Api::all(client.clone()),
+    };
+    let cluster_templates: Api<ClusterDevnetTemplate> = Api::all(client);
+
+    let lp = ListParams::default();
+    let mut out: Vec<TemplateOut> = templates
+        .list(&lp)
+        .await?
+        .into_iter()
+        .map(TemplateOut::from)
+        .collect();
+    out.extend(
+        cluster_templates
+            .list(&lp)
+            .await?
+            .into_iter()
+            .map(TemplateOut::from),
+    );
+
+    let table = Table::new(out).with(Style::empty()).to_string();
+    println!("{}", table);
+    Ok(())
+}
+
+/// Returns the devnets with their spec merged over their template, as the operator runs them.
+///
+/// Devnets whose template doesn't exist are returned unchanged.
+pub async fn merge_templates(client: Client, mut devnets: Vec<Devnet>) -> Result<Vec<Devnet>> {
+    // keyed by namespace, `None` for cluster templates, and name.
+    let mut templates: BTreeMap<(Option<String>, String), Option<DevnetSpec>> = BTreeMap::default();
+
+    for devnet in &mut devnets {
+        let template_ref = match &devnet.spec.template_ref {
+            Some(template_ref) => template_ref.clone(),
+            None => continue,
+        };
+
+        let template = match template_ref.kind.unwrap_or_default() {
+            DevnetTemplateKind::DevnetTemplate => {
+                let ns = devnet.namespace().unwrap_or_default();
+                let key = (Some(ns.clone()), template_ref.name.clone());
+                if !templates.contains_key(&key) {
+                    let api: Api<DevnetTemplate> = Api::namespaced(client.clone(), &ns);
+                    let template = api.get_opt(&template_ref.name).await?;
+                    templates.insert(key.clone(), template.map(|t| t.spec.template));
+                }
+                &templates[&key]
+            }
+            DevnetTemplateKind::ClusterDevnetTemplate => {
+                let key = (None, template_ref.name.clone());
+                if !templates.contains_key(&key) {
+                    let api: Api<ClusterDevnetTemplate> = Api::all(client.clone());
+                    let template = api.get_opt(&template_ref.name).await?;
+                    templates.insert(key.clone(), template.map(|t| t.spec.template));
+                }
+                &templates[&key]
+            }
+        };
+
+        if let Some(template) = template {
+            devnet.spec = devnet.spec.merged_over(template);
+        }
+    }
+
+    Ok(devnets)
+}
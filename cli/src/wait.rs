@@ -0,0 +1,100 @@
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Result};
+use clap::ValueEnum;
+use ryogoku_operator::{
+    kube::{
+        api::Api,
+        runtime::wait::{await_condition, conditions},
+        ResourceExt,
+    },
+    Devnet, DevnetState,
+};
+
+/// Devnet state to wait for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum WaitFor {
+    /// The devnet is running.
+    Running,
+    /// The devnet is running and its `Ready` condition is true.
+    Ready,
+    /// The devnet no longer exists.
+    Deleted,
+}
+
+/// Watch the devnet until it reaches the given state.
+///
+/// Fails if the devnet doesn't exist, unless waiting for its deletion, if the timeout expires
+/// first, or if the devnet ends up `Errored` or deleted.
+pub async fn wait(
+    devnets: Api<Devnet>,
+    name: &str,
+    wait_for: WaitFor,
+    timeout: Duration,
+) -> Result<()> {
+    let timed_out = || {
+        anyhow!(
+            "timed out after {} waiting for devnet {} to be {:?}",
+            humantime::format_duration(timeout),
+            name,
+            wait_for
+        )
+    };
+
+    let existing = devnets.get_opt(name).await?;
+    if wait_for == WaitFor::Deleted {
+        let uid = match existing {
+            Some(devnet) => devnet.uid().unwrap_or_default(),
+            None => {
+                println!("devnet {} deleted", name);
+                return Ok(());
+            }
+        };
+        let cond = conditions::is_deleted(&uid);
+        tokio::time::timeout(timeout, await_condition(devnets, name, cond))
+            .await
+            .map_err(|_| timed_out())??;
+        println!("devnet {} deleted", name);
+        return Ok(());
+    }
+    if existing.is_none() {
+        bail!("devnet {} not found", name);
+    }
+
+    // stop waiting if the devnet is deleted.
+    let cond = move |devnet: Option<&Devnet>| match devnet {
+        Some(devnet) => {
+            devnet.state() == DevnetState::Errored
+                || match wait_for {
+                    WaitFor::Running => devnet.state() == DevnetState::Running,
+                    WaitFor::Ready => devnet.is_ready(),
+                    WaitFor::Deleted => false,
+                }
+        }
+        None => true,
+    };
+
+    let devnet = tokio::time::timeout(timeout, await_condition(devnets, name, cond))
+        .await
+        .map_err(|_| timed_out())??
+        .ok_or_else(|| anyhow!("devnet {} was deleted", name))?;
+
+    if devnet.state() == DevnetState::Errored {
+        let reason = devnet
+            .status
+            .as_ref()
+            .and_then(|status| status.condition("Ready"))
+            .map(|c| {
+                let reason = c.reason.clone().unwrap_or_default();
+                match &c.message {
+                    Some(message) => format!("{}: {}", reason, message),
+                    None => reason,
+                }
+            })
+            .unwrap_or_else(|| "unknown reason".to_string());
+        bail!("devnet {} errored: {}", name, reason);
+    }
+
+    println!("devnet {} is {:?}", name, wait_for);
+    Ok(())
+}
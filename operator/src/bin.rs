@@ -7,6 +7,18 @@ use ryogoku_operator::controller;
 async fn main() -> Result<()> {
     env_logger::init();
 
+    #[cfg(feature = "metrics")]
+    {
+        let addr = std::env::var("RYOGOKU_METRICS_ADDR")
+            .unwrap_or_else(|_| "0.0.0.0:9090".to_string())
+            .parse()?;
+        tokio::spawn(async move {
+            if let Err(err) = ryogoku_operator::metrics::serve(addr).await {
+                tracing::error!(error = %err, "metrics server failed");
+            }
+        });
+    }
+
     let client = Client::try_default().await?;
     let controller_task = controller::init(client).await?;
 
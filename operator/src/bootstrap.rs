@@ -0,0 +1,138 @@
+use std::collections::BTreeMap;
+
+use schemars::{gen::SchemaGenerator, schema::Schema, JsonSchema};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::dojo::DOJO_STEP;
+
+/// Step run once the devnet is ready, before it is marked `Ready`.
+///
+/// Steps run as jobs with the `STARKNET_RPC`, `STARKNET_GATEWAY_URL`, `STARKNET_CHAIN_ID`,
+/// `STARKNET_ACCOUNT_ADDRESS`, `STARKNET_PUBLIC_KEY` and `STARKNET_PRIVATE_KEY` environment
+/// variables, the account being the first pre-deployed account. The private key comes from a
+/// Secret owned by the devnet. A step can record its results by writing
+/// `{"class_hashes": {...}, "addresses": {...}}` to `/dev/termination-log`.
+#[derive(Deserialize, Serialize, Clone, Debug, JsonSchema, Default)]
+pub struct BootstrapStep {
+    /// Step name, unique in the devnet. Lowercase alphanumeric characters and `-`, at most 63
+    /// characters. `dojo-migrate` is reserved for the Dojo world migration.
+    #[schemars(schema_with = "step_name_schema")]
+    pub name: String,
+    /// Container image running the step. Exclusive with `contracts`.
+    pub image: Option<String>,
+    /// Container entrypoint.
+    pub command: Option<Vec<String>>,
+    /// Container arguments.
+    pub args: Option<Vec<String>>,
+    /// Declare and deploy contracts with the devnet image. Exclusive with `image`.
+    pub contracts: Option<BootstrapContracts>,
+}
+
+/// Step names are label values, and the Dojo migration step name is rejected at admission.
+fn step_name_schema(gen: &mut SchemaGenerator) -> Schema {
+    let mut schema = gen.subschema_for::<String>().into_object();
+    let string = schema.string();
+    string.pattern = Some(r"^[a-z0-9]([-a-z0-9]*[a-z0-9])?$".to_string());
+    string.min_length = Some(1);
+    string.max_length = Some(63);
+    schema.extensions.insert(
+        "x-kubernetes-validations".to_string(),
+        json!([{
+            "rule": format!("self != '{}'", DOJO_STEP),
+            "message": format!("{} is reserved for the Dojo world migration", DOJO_STEP),
+        }]),
+    );
+    schema.into()
+}
+
+/// Contracts declared and deployed by a built-in step.
+#[derive(Deserialize, Serialize, Clone, Debug, JsonSchema, Default)]
+pub struct BootstrapContracts {
+    /// ConfigMap holding the contract artifacts, mounted in `/artifacts`.
+    pub config_map: Option<String>,
+    /// Image holding the contract artifacts in `/artifacts`. The image must provide `cp`.
+    pub image: Option<String>,
+    /// Contract classes to declare.
+    pub declare: Option<Vec<BootstrapDeclare>>,
+    /// Contracts to deploy, after the classes are declared.
+    pub deploy: Option<Vec<BootstrapDeploy>>,
+}
+
+/// Contract class to declare.
+#[derive(Deserialize, Serialize, Clone, Debug, JsonSchema, Default)]
+pub struct BootstrapDeclare {
+    /// Name of the class hash in the status.
+    pub name: String,
+    /// Path of the compiled contract, relative to `/artifacts`.
+    pub artifact: String,
+}
+
+/// Contract to deploy.
+#[derive(Deserialize, Serialize, Clone, Debug, JsonSchema, Default)]
+pub struct BootstrapDeploy {
+    /// Name of the contract address in the status.
+    pub name: String,
+    /// Name of a class declared by the step, or a class hash.
+    pub class: String,
+    /// Constructor calldata.
+    pub calldata: Option<Vec<String>>,
+}
+
+/// Bootstrap progress of the devnet.
+#[derive(Deserialize, Serialize, Clone, Default, Debug, JsonSchema, PartialEq)]
+pub struct BootstrapStatus {
+    /// Uid of the pod the steps ran against.
+    pub pod_uid: Option<String>,
+    /// Status of the steps that started.
+    pub steps: Vec<BootstrapStepStatus>,
+}
+
+/// Bootstrap step status.
+#[derive(Deserialize, Serialize, Clone, Default, Debug, JsonSchema, PartialEq)]
+pub struct BootstrapStepStatus {
+    /// Step name.
+    pub name: String,
+    /// Step phase.
+    pub phase: BootstrapPhase,
+    /// Name of the job running the step.
+    pub job: Option<String>,
+    /// Details about the step failure.
+    pub message: Option<String>,
+    /// Class hashes recorded by the step, by name.
+    pub class_hashes: Option<BTreeMap<String, String>>,
+    /// Contract addresses recorded by the step, by name.
+    pub addresses: Option<BTreeMap<String, String>>,
+}
+
+/// Phase of a bootstrap step.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, JsonSchema, PartialEq, Eq, Default)]
+pub enum BootstrapPhase {
+    /// Waiting for the previous steps.
+    #[default]
+    Pending,
+    /// The step job is running.
+    Running,
+    /// The step job succeeded.
+    Succeeded,
+    /// The step job failed.
+    Failed,
+}
+
+/// Results written by a step to its termination message.
+#[derive(Deserialize, Serialize, Clone, Default, Debug)]
+pub struct BootstrapResults {
+    /// Class hashes, by name.
+    #[serde(default)]
+    pub class_hashes: BTreeMap<String, String>,
+    /// Contract addresses, by name.
+    #[serde(default)]
+    pub addresses: BTreeMap<String, String>,
+}
+
+impl BootstrapStatus {
+    /// Returns the status of the step with the given name.
+    pub fn step(&self, name: &str) -> Option<&BootstrapStepStatus> {
+        self.steps.iter().find(|step| step.name == name)
+    }
+}
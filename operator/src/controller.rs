@@ -1,15 +1,35 @@
+mod activity;
+mod apply;
+mod bootstrap;
+mod claim;
+mod clone;
+mod dojo;
+mod health;
+mod l1;
+mod pool;
+mod startup;
+
 use std::{collections::BTreeMap, sync::Arc, time::Duration};
 
 use futures::{future::BoxFuture, FutureExt, StreamExt};
 use k8s_openapi::{
     api::{self, core::v1::ServicePort},
-    apimachinery::{self, pkg::apis::meta},
+    apimachinery::{
+        self,
+        pkg::apis::meta::{self, v1::Time},
+    },
+    chrono::Utc,
 };
 use kube::{
-    api::{DeleteParams, ListParams, Patch, PatchParams, PostParams},
+    api::{
+        ApiResource, DeleteParams, DynamicObject, GroupVersionKind, ListParams, Patch, PatchParams,
+        PostParams,
+    },
     runtime::{
         controller::Action,
+        events::Reporter,
         finalizer::{self, Event as Finalizer},
+        reflector::{ObjectRef, Store},
         Controller,
     },
     Api, Client, CustomResourceExt, ResourceExt,
@@ -18,19 +38,50 @@ use serde_json::json;
 use tracing::{debug, error, info, warn};
 
 use crate::{
-    devnet::{Devnet, DevnetState, DevnetStatus},
+    devnet::{
+        Devnet, DevnetCondition, DevnetEndpoints, DevnetExposure, DevnetState, DevnetStatus,
+        DevnetStorage, ExposureMode, RESTART_ANNOTATION,
+    },
     error::Result,
+    pool::{DevnetClaim, DevnetPool, POOL_LABEL},
+    rpc::DevnetClient,
+    template::{ClusterDevnetTemplate, DevnetTemplate, DevnetTemplateKind},
     Error,
 };
 
 static DEVNET_FINALIZER: &str = "devnets.ryogoku.stark";
 static DEFAULT_IMAGE: &str = "shardlabs/starknet-devnet:latest";
+/// Name of the devnet container in the devnet pod.
+static DEVNET_CONTAINER: &str = "starknet-devnet";
+const RPC_PORT: i32 = 9575;
+const GATEWAY_PORT: i32 = 5050;
+/// Path of the JSON-RPC endpoint on the rpc port.
+static RPC_PATH: &str = "/rpc";
+/// Mount path of the devnet storage.
+static DATA_PATH: &str = "/data";
+/// Path of the devnet state dump.
+static DUMP_PATH: &str = "/data/devnet.dump";
+/// How often idle devnets are checked for new blocks and requests.
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+/// Container waiting reasons of devnets that can't start.
+static FAILED_WAITING_REASONS: &[&str] = &[
+    "CrashLoopBackOff",
+    "ErrImagePull",
+    "ImagePullBackOff",
+    "InvalidImageName",
+    "CreateContainerConfigError",
+    "CreateContainerError",
+];
+/// How often the startup settings and loading the L1 messaging contract are retried.
+const STARTUP_RETRY_INTERVAL: Duration = Duration::from_secs(10);
 
 /// Reconciler context.
 #[derive(Clone)]
 pub struct Context {
     /// kube client.
     pub client: Client,
+    /// Reporter of the events published by the operator.
+    pub reporter: Reporter,
 }
 
 /// Reconcile devnet state.
@@ -44,6 +95,17 @@ async fn reconcile_devnet(devnet: Arc<Devnet>, ctx: Arc<Context>) -> Result<Acti
         "reconcile devnet"
     );
 
+    // deleting devnets don't need their template, which may be gone already.
+    let devnet = if devnet.metadata.deletion_timestamp.is_none() {
+        match with_template(&devnet, &ctx).await? {
+            Some(devnet) => Arc::new(devnet),
+            // templates are watched, the devnet is reconciled again once it's created.
+            None => return devnet.template_not_found(ctx).await,
+        }
+    } else {
+        devnet
+    };
+
     finalizer::finalizer(&devnets, DEVNET_FINALIZER, devnet, |event| async {
         match event {
             Finalizer::Apply(devnet) => devnet.reconcile(ctx.clone()).await,
@@ -54,116 +116,593 @@ async fn reconcile_devnet(devnet: Arc<Devnet>, ctx: Arc<Context>) -> Result<Acti
     .map_err(|err| Error::Finalizer(Box::new(err)))
 }
 
+/// Returns the devnet with its spec merged over the referenced template, if any.
+///
+/// Returns `None` if the template doesn't exist.
+async fn with_template(devnet: &Devnet, ctx: &Context) -> Result<Option<Devnet>> {
+    let template_ref = match &devnet.spec.template_ref {
+        Some(template_ref) => template_ref,
+        None => return Ok(Some(devnet.clone())),
+    };
+
+    let template = match template_ref.kind.unwrap_or_default() {
+        DevnetTemplateKind::DevnetTemplate => {
+            let ns = devnet.namespace().expect("devnet is namespaced");
+            let templates: Api<DevnetTemplate> = Api::namespaced(ctx.client.clone(), &ns);
+            templates
+                .get_opt(&template_ref.name)
+                .await?
+                .map(|template| template.spec.template)
+        }
+        DevnetTemplateKind::ClusterDevnetTemplate => {
+            let templates: Api<ClusterDevnetTemplate> = Api::all(ctx.client.clone());
+            templates
+                .get_opt(&template_ref.name)
+                .await?
+                .map(|template| template.spec.template)
+        }
+    };
+    let template = match template {
+        Some(template) => template,
+        None => return Ok(None),
+    };
+
+    let mut devnet = devnet.clone();
+    devnet.spec = devnet.spec.merged_over(&template);
+    Ok(Some(devnet))
+}
+
+/// Returns the devnets referencing the template. Namespaced templates are only referenced by
+/// devnets in their namespace.
+fn template_devnets(
+    devnets: &Store<Devnet>,
+    kind: DevnetTemplateKind,
+    namespace: Option<&str>,
+    name: &str,
+) -> Vec<ObjectRef<Devnet>> {
+    devnets
+        .state()
+        .iter()
+        .filter(|devnet| {
+            let references = devnet
+                .spec
+                .template_ref
+                .as_ref()
+                .map(|r| r.name == name && r.kind.unwrap_or_default() == kind)
+                .unwrap_or(false);
+            references && (namespace.is_none() || devnet.namespace().as_deref() == namespace)
+        })
+        .map(|devnet| ObjectRef::from_obj(devnet.as_ref()))
+        .collect()
+}
+
 impl Devnet {
-    async fn reconcile(&self, ctx: Arc<Context>) -> Result<Action> {
-        let ns = self.namespace().expect("devnet is namespaced");
-        let name = self.name_any();
-        let devnets: Api<Devnet> = Api::namespaced(ctx.client.clone(), &ns);
+    /// Report the missing template in the `Ready` condition.
+    async fn template_not_found(&self, ctx: Arc<Context>) -> Result<Action> {
+        let template_ref = self
+            .spec
+            .template_ref
+            .as_ref()
+            .expect("devnet has a template");
+        let message = format!(
+            "{:?} {} not found",
+            template_ref.kind.unwrap_or_default(),
+            template_ref.name
+        );
+        warn!(
+            devnet = self.name_any(),
+            namespace = self.metadata.namespace,
+            template = template_ref.name,
+            "template not found"
+        );
+
+        let mut status = self.status.clone().unwrap_or_default();
+        status.set_condition(DevnetCondition::new(
+            "Ready",
+            false,
+            "TemplateNotFound",
+            Some(message),
+        ));
+        self.update_status(ctx, status).await?;
+        Ok(Action::await_change())
+    }
 
+    async fn reconcile(&self, ctx: Arc<Context>) -> Result<Action> {
         debug!(state = ?self.state(), "reconcile from state");
 
+        // devnets without storage can't be paused, they keep running with a Paused condition.
+        if self.paused() && self.spec.storage.is_some() && self.state() != DevnetState::Paused {
+            return self.pause(ctx.clone()).await;
+        }
+
         match self.state() {
-            DevnetState::Created => {
+            DevnetState::Created => self.start(ctx.clone()).await,
+            DevnetState::Running => {
+                if let Some(requested) = self.restart_requested() {
+                    return self.restart(ctx.clone(), requested).await;
+                }
+
+                // Check storage is still there
+                self.setup_storage(ctx.clone()).await?;
+
+                // Check pod is still running, it may be terminating, recreated or failing
                 let pod = self.setup_pods(ctx.clone()).await?;
+                let state = pod_state(&pod);
+                if state != DevnetState::Running {
+                    warn!(
+                        pod = pod.name_any(),
+                        namespace = pod.metadata.namespace,
+                        state = %state,
+                        "pod is no longer running"
+                    );
+                    return self.start(ctx.clone()).await;
+                }
+
+                // Check service is still running
                 let service = self.setup_service(ctx.clone()).await?;
 
-                // update status
-                let new_status = json!({
-                    "apiVersion": "ryogoku.stark/v1",
-                    "kind": "Devnet",
-                    "status": DevnetStatus {
-                        state: DevnetState::Running,
-                    },
-                });
+                // Check exposure is still in place, it may have been added after creation
+                let public_url = self.setup_exposure(ctx.clone()).await?;
 
-                let pp = PatchParams::apply("ryogoku").force();
-                devnets
-                    .patch_status(&name, &pp, &Patch::Apply(new_status))
-                    .await?;
+                // Refresh endpoints, load balancer and node addresses are assigned asynchronously
+                let endpoints = self.endpoints(&pod, &service);
 
-                info!(
-                    pod = pod.name_any(),
-                    service = service.name_any(),
-                    namespace = pod.metadata.namespace,
-                    "updating status to Running"
-                );
+                let mut status = self.status.clone().unwrap_or_default();
+                status.state = state;
+                status.public_url = public_url;
+                status.endpoints = Some(endpoints);
+                self.set_paused_condition(&mut status);
 
-                // check again in 5 minutes
-                Ok(Action::requeue(Duration::from_secs(5 * 60)))
-            }
-            DevnetState::Running => {
-                // Check pod is still running
-                self.setup_pods(ctx.clone()).await?;
+                // Load the L1 messaging contract before running the bootstrap steps
+                self.setup_l1_messaging(ctx.clone(), &pod, &mut status)
+                    .await;
 
-                // Check service is still running
-                self.setup_service(ctx.clone()).await?;
+                // Apply the startup settings, like the devnet time
+                self.setup_startup(ctx.clone(), &pod, &mut status).await;
+
+                // Run the bootstrap steps, the devnet is ready once they succeed
+                if let Err(err) = self.validate_bootstrap() {
+                    return self
+                        .invalid_bootstrap(ctx.clone(), status, err.to_string())
+                        .await;
+                }
+                self.bootstrap(ctx.clone(), &pod, &mut status).await?;
+                status.set_condition(self.ready_condition(&pod, &status));
+
+                // Check the devnet answers on its rpc endpoint
+                self.check_health(ctx.clone(), &pod, &mut status).await;
 
-                Ok(Action::await_change())
+                // Start Torii once the Dojo world is migrated
+                status.dojo = self.setup_dojo(ctx.clone(), &status).await?;
+
+                // Delete the devnet once its time-to-live expires
+                let ready = status.condition("Ready").map(|c| c.is_true());
+                if self.spec.ttl_seconds_after_idle.is_some() && ready == Some(true) {
+                    self.observe_activity(ctx.clone(), &pod, &mut status).await;
+                }
+                status.expires_at = self.expiry(status.last_activity_time.as_ref());
+                if let Some(expires_at) = &status.expires_at {
+                    if expires_at.0 <= Utc::now() {
+                        return self.expire(ctx.clone()).await;
+                    }
+                }
+
+                self.update_status(ctx.clone(), status.clone()).await?;
+
+                // Retry the startup settings and the L1 messaging contract while the pod starts
+                let pending = self.l1_pending(&pod, &status) || self.startup_pending(&pod, &status);
+                if pending && pod_ready_condition(&pod).is_true() {
+                    return Ok(Action::requeue(STARTUP_RETRY_INTERVAL));
+                }
+
+                Ok(self.next_check(&status))
             }
             DevnetState::Errored => {
-                // container did not start.
-                todo!()
+                if let Some(requested) = self.restart_requested() {
+                    return self.restart(ctx.clone(), requested).await;
+                }
+
+                self.recreate_failed_pod(ctx.clone()).await?;
+                self.start(ctx.clone()).await
+            }
+            DevnetState::Paused => {
+                if !self.paused() {
+                    // A restart requested while paused starts from genesis instead of the dump
+                    if let Some(requested) = self.restart_requested() {
+                        return self.restart(ctx.clone(), requested).await;
+                    }
+                    return self.resume(ctx.clone()).await;
+                }
+
+                // Keep the service, so that it keeps its ip and node ports
+                self.setup_service(ctx.clone()).await?;
+
+                // Paused devnets still expire
+                let mut status = self.status.clone().unwrap_or_default();
+                status.expires_at = self.expiry(status.last_activity_time.as_ref());
+                if let Some(expires_at) = &status.expires_at {
+                    if expires_at.0 <= Utc::now() {
+                        return self.expire(ctx.clone()).await;
+                    }
+                }
+                self.update_status(ctx.clone(), status.clone()).await?;
+
+                Ok(self.next_check(&status))
             }
         }
     }
 
-    async fn setup_pods(&self, ctx: Arc<Context>) -> Result<api::core::v1::Pod> {
-        let ns = self.namespace().expect("devnet is namespaced");
-        let pods: Api<api::core::v1::Pod> = Api::namespaced(ctx.client.clone(), &ns);
+    /// Create the devnet objects and derive the devnet state from its pod.
+    ///
+    /// The devnet is `Running` once its container runs, and `Errored` if it can't start.
+    /// Owned objects are watched, so the devnet is reconciled again when the pod changes.
+    async fn start(&self, ctx: Arc<Context>) -> Result<Action> {
+        self.setup_storage(ctx.clone()).await?;
+        let pod = self.setup_pods(ctx.clone()).await?;
+        let service = self.setup_service(ctx.clone()).await?;
+        let public_url = self.setup_exposure(ctx.clone()).await?;
+        let endpoints = self.endpoints(&pod, &service);
+
+        // update status
+        let mut status = self.status.clone().unwrap_or_default();
+        status.state = pod_state(&pod);
+        // the new pod already satisfies the restart annotation
+        status.restarted_at = self.annotations().get(RESTART_ANNOTATION).cloned();
+        status.public_url = public_url;
+        status.endpoints = Some(endpoints);
+        status.set_condition(self.ready_condition(&pod, &status));
+        self.set_paused_condition(&mut status);
+        if !pod_ready_condition(&pod).is_true() {
+            self.set_pod_not_ready(&mut status);
+        }
 
-        // create pod
-        let existing = pods.get_opt(&self.name_any()).await?;
+        // devnets that can't start still expire
+        status.expires_at = self.expiry(status.last_activity_time.as_ref());
+        if let Some(expires_at) = &status.expires_at {
+            if expires_at.0 <= Utc::now() {
+                return self.expire(ctx.clone()).await;
+            }
+        }
 
-        if let Some(pod) = existing {
+        if self.status.as_ref().map(|s| s.state) != Some(status.state) {
             info!(
                 pod = pod.name_any(),
+                service = service.name_any(),
                 namespace = pod.metadata.namespace,
-                "pod already exists"
+                state = %status.state,
+                "updating status from pod"
             );
-            Ok(pod)
-        } else {
-            let pod_manifest = self.pod_manifest();
-            let pp = PostParams::default();
-            let pod = pods.create(&pp, &pod_manifest).await?;
+        }
+        self.update_status(ctx.clone(), status.clone()).await?;
+
+        Ok(self.next_check(&status))
+    }
+
+    /// Delete the pod if it terminated, like when it's evicted, so that it's created again.
+    async fn recreate_failed_pod(&self, ctx: Arc<Context>) -> Result<()> {
+        let ns = self.namespace().expect("devnet is namespaced");
+        let pods: Api<api::core::v1::Pod> = Api::namespaced(ctx.client.clone(), &ns);
+
+        let pod = match pods.get_opt(&self.name_any()).await? {
+            Some(pod) => pod,
+            None => return Ok(()),
+        };
+        let phase = pod.status.as_ref().and_then(|s| s.phase.as_deref());
+        if pod.metadata.deletion_timestamp.is_none()
+            && matches!(phase, Some("Failed" | "Succeeded"))
+        {
+            pods.delete(&pod.name_any(), &DeleteParams::default())
+                .await?;
             info!(
                 pod = pod.name_any(),
                 namespace = pod.metadata.namespace,
-                "pod created"
+                "pod terminated, recreating"
             );
-            Ok(pod)
         }
+        Ok(())
     }
 
-    async fn setup_service(&self, ctx: Arc<Context>) -> Result<api::core::v1::Service> {
+    /// Dump the devnet state to its storage and delete its pod.
+    async fn pause(&self, ctx: Arc<Context>) -> Result<Action> {
         let ns = self.namespace().expect("devnet is namespaced");
-        let services: Api<api::core::v1::Service> = Api::namespaced(ctx.client.clone(), &ns);
+        let pods: Api<api::core::v1::Pod> = Api::namespaced(ctx.client.clone(), &ns);
+
+        let mut status = self.status.clone().unwrap_or_default();
+
+        if let Some(pod) = pods.get_opt(&self.name_any()).await? {
+            let running = pod.status.as_ref().and_then(|s| s.phase.as_deref()) == Some("Running");
+            if running {
+                let client = DevnetClient::for_operator(ctx.client.clone(), &ns, &self.name_any());
+                let _: serde_json::Value = client
+                    .post("gateway", "dump", &json!({ "path": DUMP_PATH }))
+                    .await?;
+                status.dump_path = Some(DUMP_PATH.to_string());
+                info!(
+                    devnet = self.name_any(),
+                    namespace = self.metadata.namespace,
+                    path = DUMP_PATH,
+                    "devnet state dumped"
+                );
+            }
+
+            pods.delete(&pod.name_any(), &DeleteParams::default())
+                .await?;
+        }
 
-        // create service now that pod is running
-        let existing = services.get_opt(&self.name_any()).await?;
+        status.state = DevnetState::Paused;
+        status.set_condition(DevnetCondition::new(
+            "Ready",
+            false,
+            "Paused",
+            Some("devnet is paused".to_string()),
+        ));
+        status.set_condition(DevnetCondition::new("Paused", true, "Paused", None));
+        self.update_status(ctx, status).await?;
 
-        if let Some(service) = existing {
+        info!(
+            devnet = self.name_any(),
+            namespace = self.metadata.namespace,
+            "devnet paused"
+        );
+        Ok(Action::await_change())
+    }
+
+    /// Report running devnets in the `Paused` condition, and pause requests that can't be
+    /// satisfied without storage.
+    fn set_paused_condition(&self, status: &mut DevnetStatus) {
+        if self.paused() && self.spec.storage.is_none() {
+            status.set_condition(DevnetCondition::new(
+                "Paused",
+                false,
+                "StorageRequired",
+                Some("storage is required to pause a devnet".to_string()),
+            ));
+        } else if status.condition("Paused").is_some() {
+            status.set_condition(DevnetCondition::new("Paused", false, "NotPaused", None));
+        }
+    }
+
+    /// Restart the devnet from its state dump.
+    async fn resume(&self, ctx: Arc<Context>) -> Result<Action> {
+        // the pod is recreated with the dump from the Created state.
+        let mut status = self.status.clone().unwrap_or_default();
+        status.state = DevnetState::Created;
+        self.update_status(ctx, status).await?;
+
+        info!(
+            devnet = self.name_any(),
+            namespace = self.metadata.namespace,
+            "devnet resumed"
+        );
+        Ok(Action::await_change())
+    }
+
+    /// Delete the pod to restart the devnet from genesis, keeping its service.
+    async fn restart(&self, ctx: Arc<Context>, requested: &str) -> Result<Action> {
+        let ns = self.namespace().expect("devnet is namespaced");
+        let pods: Api<api::core::v1::Pod> = Api::namespaced(ctx.client.clone(), &ns);
+
+        if pods.get_opt(&self.name_any()).await?.is_some() {
+            pods.delete(&self.name_any(), &DeleteParams::default())
+                .await?;
+        }
+
+        // the pod is recreated from the Created state, without the state dump.
+        let mut status = self.status.clone().unwrap_or_default();
+        status.state = DevnetState::Created;
+        status.dump_path = None;
+        status.restart_count = Some(status.restart_count.unwrap_or(0) + 1);
+        status.last_restart_time = Some(Time(Utc::now()));
+        status.restarted_at = Some(requested.to_string());
+        status.set_condition(DevnetCondition::new(
+            "Ready",
+            false,
+            "Restarting",
+            Some("devnet is restarting".to_string()),
+        ));
+        self.update_status(ctx, status).await?;
+
+        info!(
+            devnet = self.name_any(),
+            namespace = self.metadata.namespace,
+            "devnet restarted"
+        );
+        Ok(Action::await_change())
+    }
+
+    /// Create the volume storing the devnet state, if the devnet has storage.
+    async fn setup_storage(&self, ctx: Arc<Context>) -> Result<()> {
+        let storage = match &self.spec.storage {
+            Some(storage) => storage,
+            None => return Ok(()),
+        };
+
+        let ns = self.namespace().expect("devnet is namespaced");
+        let claims: Api<api::core::v1::PersistentVolumeClaim> =
+            Api::namespaced(ctx.client.clone(), &ns);
+
+        let existing = claims.get_opt(&self.storage_claim_name()).await?;
+
+        if let Some(claim) = existing {
             info!(
-                service = service.name_any(),
-                namespace = service.metadata.namespace,
-                "service already exists"
+                claim = claim.name_any(),
+                namespace = claim.metadata.namespace,
+                "volume claim already exists"
             );
-            Ok(service)
         } else {
-            let service_manifest = self.service_manifest();
+            let claim_manifest = self.storage_claim_manifest(storage);
             let pp = PostParams::default();
-            let service = services.create(&pp, &service_manifest).await?;
+            let claim = claims.create(&pp, &claim_manifest).await?;
             info!(
-                service = service.name_any(),
-                namespace = service.metadata.namespace,
-                "service created"
+                claim = claim.name_any(),
+                namespace = claim.metadata.namespace,
+                "volume claim created"
             );
-            Ok(service)
         }
+
+        Ok(())
+    }
+
+    /// Delete the expired devnet.
+    async fn expire(&self, ctx: Arc<Context>) -> Result<Action> {
+        let ns = self.namespace().expect("devnet is namespaced");
+        let devnets: Api<Devnet> = Api::namespaced(ctx.client.clone(), &ns);
+
+        devnets
+            .delete(&self.name_any(), &DeleteParams::default())
+            .await?;
+        info!(
+            devnet = self.name_any(),
+            namespace = self.metadata.namespace,
+            "devnet expired, deleted"
+        );
+
+        Ok(Action::await_change())
+    }
+
+    /// Returns when to check the devnet expiry and health again.
+    fn next_check(&self, status: &DevnetStatus) -> Action {
+        let until_expiry = status
+            .expires_at
+            .as_ref()
+            .and_then(|expires_at| (expires_at.0 - Utc::now()).to_std().ok());
+        // paused devnets produce no blocks, their idle expiry is already known.
+        let idle_check = self
+            .spec
+            .ttl_seconds_after_idle
+            .filter(|_| !self.keep() && status.state != DevnetState::Paused)
+            .map(|_| IDLE_CHECK_INTERVAL);
+        // checks skipped while the pod is not ready are retried when the pod changes.
+        let health_check =
+            (status.state == DevnetState::Running).then(|| match self.until_health_check(status) {
+                until if until.is_zero() => health::HEALTH_CHECK_INTERVAL,
+                until => until,
+            });
+
+        match [until_expiry, idle_check, health_check]
+            .into_iter()
+            .flatten()
+            .min()
+        {
+            Some(next) => Action::requeue(next),
+            None => Action::await_change(),
+        }
+    }
+
+    /// Update the devnet status, skipping the request if nothing but the health check times
+    /// changed.
+    async fn update_status(&self, ctx: Arc<Context>, status: DevnetStatus) -> Result<()> {
+        let unchanged = self.status.as_ref().map(|current| {
+            current == &status || health::only_check_times_changed(current, &status)
+        });
+        if unchanged == Some(true) {
+            return Ok(());
+        }
+
+        let ns = self.namespace().expect("devnet is namespaced");
+        let devnets: Api<Devnet> = Api::namespaced(ctx.client.clone(), &ns);
+
+        let new_status = json!({
+            "apiVersion": "ryogoku.stark/v1",
+            "kind": "Devnet",
+            "status": status,
+        });
+
+        let pp = PatchParams::apply("ryogoku").force();
+        devnets
+            .patch_status(&self.name_any(), &pp, &Patch::Apply(new_status))
+            .await?;
+
+        Ok(())
+    }
+
+    async fn setup_pods(&self, ctx: Arc<Context>) -> Result<api::core::v1::Pod> {
+        let ns = self.namespace().expect("devnet is namespaced");
+        let pods: Api<api::core::v1::Pod> = Api::namespaced(ctx.client.clone(), &ns);
+
+        self.apply_owned(ctx.clone(), &pods, &self.pod_manifest())
+            .await
+    }
+
+    async fn setup_service(&self, ctx: Arc<Context>) -> Result<api::core::v1::Service> {
+        let ns = self.namespace().expect("devnet is namespaced");
+        let services: Api<api::core::v1::Service> = Api::namespaced(ctx.client.clone(), &ns);
+
+        self.apply_owned(ctx.clone(), &services, &self.service_manifest())
+            .await
+    }
+
+    /// Expose the devnet according to `spec.exposure`, returning its public url.
+    ///
+    /// The Ingress or HTTPRoute of a previous exposure is deleted when the exposure is removed
+    /// or its mode changes.
+    async fn setup_exposure(&self, ctx: Arc<Context>) -> Result<Option<String>> {
+        let mode = self.spec.exposure.as_ref().map(|exposure| exposure.mode());
+        let exposed = self.status.as_ref().and_then(|s| s.public_url.as_ref());
+        if exposed.is_some() {
+            if mode != Some(ExposureMode::Ingress) {
+                self.delete_ingress(ctx.clone()).await?;
+            }
+            if mode != Some(ExposureMode::HttpRoute) {
+                self.delete_http_route(ctx.clone()).await?;
+            }
+        }
+
+        let exposure = match &self.spec.exposure {
+            Some(exposure) => exposure,
+            None => return Ok(None),
+        };
+
+        match exposure.mode() {
+            ExposureMode::Ingress => self.setup_ingress(ctx, exposure).await?,
+            ExposureMode::HttpRoute => self.setup_http_route(ctx, exposure).await?,
+        }
+
+        let ns = self.namespace().expect("devnet is namespaced");
+        Ok(Some(exposure.url_for(&self.name_any(), &ns)))
+    }
+
+    async fn setup_ingress(&self, ctx: Arc<Context>, exposure: &DevnetExposure) -> Result<()> {
+        let ns = self.namespace().expect("devnet is namespaced");
+        let ingresses: Api<api::networking::v1::Ingress> = Api::namespaced(ctx.client.clone(), &ns);
+
+        self.apply_owned(ctx.clone(), &ingresses, &self.ingress_manifest(exposure))
+            .await?;
+        Ok(())
+    }
+
+    async fn setup_http_route(&self, ctx: Arc<Context>, exposure: &DevnetExposure) -> Result<()> {
+        let ns = self.namespace().expect("devnet is namespaced");
+        let api_resource = http_route_api_resource();
+        let routes: Api<DynamicObject> =
+            Api::namespaced_with(ctx.client.clone(), &ns, &api_resource);
+
+        let route_manifest = self.http_route_manifest(exposure)?;
+        self.apply_owned_with(ctx.clone(), &routes, &route_manifest, &api_resource)
+            .await?;
+        Ok(())
+    }
+
+    async fn delete_ingress(&self, ctx: Arc<Context>) -> Result<()> {
+        let ns = self.namespace().expect("devnet is namespaced");
+        let ingresses: Api<api::networking::v1::Ingress> = Api::namespaced(ctx.client.clone(), &ns);
+
+        self.delete_owned(&ingresses, &self.name_any()).await
     }
 
+    async fn delete_http_route(&self, ctx: Arc<Context>) -> Result<()> {
+        let ns = self.namespace().expect("devnet is namespaced");
+        let routes: Api<DynamicObject> =
+            Api::namespaced_with(ctx.client.clone(), &ns, &http_route_api_resource());
+
+        self.delete_owned(&routes, &self.name_any()).await
+    }
 
     async fn cleanup(&self, ctx: Arc<Context>) -> Result<Action> {
         debug!("cleanup devnet");
+        #[cfg(feature = "metrics")]
+        crate::metrics::remove(self);
+
         let ns = self.namespace().expect("devnet is namespaced");
         let pods: Api<api::core::v1::Pod> = Api::namespaced(ctx.client.clone(), &ns);
         let dp = DeleteParams::default();
@@ -223,71 +762,98 @@ impl Devnet {
     }
 
     fn pod_spec(&self) -> api::core::v1::PodSpec {
-        use api::core::v1::{Container, ContainerPort, PodSpec};
+        use api::core::v1::{
+            Container, ContainerPort, PersistentVolumeClaimVolumeSource, PodSpec, Volume,
+            VolumeMount,
+        };
         let image = self
             .spec
             .image
             .clone()
             .unwrap_or_else(|| DEFAULT_IMAGE.to_string());
 
-        let mut args = Vec::default();
-
-        if self.spec.lite_mode.unwrap_or(false) {
-            args.push("--lite-mode".to_string());
-        }
-
-        if self.spec.lite_mode_block_hash.unwrap_or(false) {
-            args.push("--lite-mode-block-hash".to_string());
-        }
-
-        if self.spec.lite_mode_deploy_hash.unwrap_or(false) {
-            args.push("--lite-mode-deploy-hash".to_string());
-        }
-
-        if let Some(accounts) = self.spec.accounts {
-            args.push(format!("--accounts={}", accounts));
-        }
-
-        if let Some(initial_balance) = &self.spec.initial_balance {
-            args.push(format!("--initial-balance={}", initial_balance));
-        }
-
-        if let Some(seed) = &self.spec.seed {
-            args.push(format!("--seed={}", seed));
-        }
-
-        if let Some(start_time) = self.spec.start_time {
-            args.push(format!("--start-time={}", start_time));
-        }
+        let dump_path = self.status.as_ref().and_then(|s| s.dump_path.as_deref());
+        let args = self.spec.args(dump_path);
 
-        if let Some(gas_price) = &self.spec.gas_price {
-            args.push(format!("--gas-price={}", gas_price));
-        }
-
-        if let Some(extra_args) = &self.spec.extra_args {
-            args.extend(extra_args.clone());
-        }
+        let (volumes, volume_mounts) = if self.spec.storage.is_some() {
+            let volume = Volume {
+                name: "data".to_string(),
+                persistent_volume_claim: Some(PersistentVolumeClaimVolumeSource {
+                    claim_name: self.storage_claim_name(),
+                    ..PersistentVolumeClaimVolumeSource::default()
+                }),
+                ..Volume::default()
+            };
+            let mount = VolumeMount {
+                name: "data".to_string(),
+                mount_path: DATA_PATH.to_string(),
+                ..VolumeMount::default()
+            };
+            (Some(vec![volume]), Some(vec![mount]))
+        } else {
+            (None, None)
+        };
 
-        PodSpec {
+        let mut pod_spec = PodSpec {
+            volumes,
             containers: vec![Container {
-                name: "starknet-devnet".to_string(),
+                name: DEVNET_CONTAINER.to_string(),
                 image: Some(image),
                 args: Some(args),
+                volume_mounts,
                 ports: Some(vec![
                     ContainerPort {
-                        container_port: 9575,
+                        container_port: RPC_PORT,
                         name: Some("rpc".to_string()),
                         ..ContainerPort::default()
                     },
                     ContainerPort {
-                        container_port: 5050,
+                        container_port: GATEWAY_PORT,
                         name: Some("gateway".to_string()),
                         ..ContainerPort::default()
-                    }
+                    },
                 ]),
                 ..Container::default()
             }],
             ..PodSpec::default()
+        };
+
+        if let Some(l1) = &self.spec.l1 {
+            pod_spec.containers.push(l1::l1_container(l1));
+        }
+
+        pod_spec
+    }
+
+    fn storage_claim_name(&self) -> String {
+        format!("{}-data", self.name_any())
+    }
+
+    fn storage_claim_manifest(
+        &self,
+        storage: &DevnetStorage,
+    ) -> api::core::v1::PersistentVolumeClaim {
+        use api::core::v1::{
+            PersistentVolumeClaim, PersistentVolumeClaimSpec, ResourceRequirements,
+        };
+        use apimachinery::pkg::api::resource::Quantity;
+
+        let mut metadata = self.object_metadata();
+        metadata.name = Some(self.storage_claim_name());
+        let size = storage.size.clone().unwrap_or_else(|| "1Gi".to_string());
+
+        PersistentVolumeClaim {
+            metadata,
+            spec: Some(PersistentVolumeClaimSpec {
+                access_modes: Some(vec!["ReadWriteOnce".to_string()]),
+                storage_class_name: storage.storage_class_name.clone(),
+                resources: Some(ResourceRequirements {
+                    requests: Some(BTreeMap::from([("storage".to_string(), Quantity(size))])),
+                    ..ResourceRequirements::default()
+                }),
+                ..PersistentVolumeClaimSpec::default()
+            }),
+            ..PersistentVolumeClaim::default()
         }
     }
 
@@ -307,26 +873,271 @@ impl Devnet {
         use api::core::v1::ServiceSpec;
         use apimachinery::pkg::util::intstr::IntOrString;
 
-        ServiceSpec {
+        let mut service_spec = ServiceSpec {
             selector: Some(BTreeMap::from([
                 ("ryogoku.stark/devnet_name".to_string(), self.name_any())
             ])),
             type_: self.spec.service_type.clone(),
             ports: Some(vec![ServicePort {
                 name: Some("rpc".to_string()),
-                port: 9575,
+                port: RPC_PORT,
                 target_port: Some(IntOrString::String("rpc".to_string())),
                 ..ServicePort::default()
             },
             ServicePort {
                 name: Some("gateway".to_string()),
-                port: 5050,
+                port: GATEWAY_PORT,
                 target_port: Some(IntOrString::String("gateway".to_string())),
                 ..ServicePort::default()
             }]),
             ..ServiceSpec::default()
+        };
+
+        if self.spec.l1.is_some() {
+            service_spec
+                .ports
+                .get_or_insert_with(Vec::new)
+                .push(l1::l1_service_port());
+        }
+
+        service_spec
+    }
+
+    /// Compute the devnet endpoints from its pod, service and exposure.
+    fn endpoints(
+        &self,
+        pod: &api::core::v1::Pod,
+        service: &api::core::v1::Service,
+    ) -> DevnetEndpoints {
+        let ns = self.namespace().expect("devnet is namespaced");
+        let host = format!("{}.{}.svc.cluster.local", self.name_any(), ns);
+
+        let mut endpoints = DevnetEndpoints {
+            rpc: format!("http://{}:{}{}", host, RPC_PORT, RPC_PATH),
+            gateway: format!("http://{}:{}", host, GATEWAY_PORT),
+            ..DevnetEndpoints::default()
+        };
+
+        let spec = service.spec.clone().unwrap_or_default();
+        let ports = spec.ports.unwrap_or_default();
+        let node_port = |name: &str| {
+            ports
+                .iter()
+                .find(|port| port.name.as_deref() == Some(name))
+                .and_then(|port| port.node_port)
+        };
+
+        match spec.type_.as_deref() {
+            Some("LoadBalancer") => {
+                let address = service
+                    .status
+                    .as_ref()
+                    .and_then(|status| status.load_balancer.as_ref())
+                    .and_then(|lb| lb.ingress.as_ref())
+                    .and_then(|ingress| ingress.first())
+                    .and_then(|ingress| ingress.hostname.clone().or_else(|| ingress.ip.clone()));
+                if let Some(address) = address {
+                    endpoints.external_rpc =
+                        Some(format!("http://{}:{}{}", address, RPC_PORT, RPC_PATH));
+                    endpoints.external_gateway =
+                        Some(format!("http://{}:{}", address, GATEWAY_PORT));
+                }
+            }
+            Some("NodePort") => {
+                // node ports are open on every node, use the one running the devnet.
+                let host_ip = pod
+                    .status
+                    .as_ref()
+                    .and_then(|status| status.host_ip.clone());
+                if let Some(host_ip) = host_ip {
+                    endpoints.external_rpc = node_port("rpc")
+                        .map(|port| format!("http://{}:{}{}", host_ip, port, RPC_PATH));
+                    endpoints.external_gateway =
+                        node_port("gateway").map(|port| format!("http://{}:{}", host_ip, port));
+                }
+            }
+            _ => {}
+        }
+
+        // an ingress or route takes precedence over the service address.
+        if let Some(exposure) = &self.spec.exposure {
+            let url = exposure.url_for(&self.name_any(), &ns);
+            match exposure.port.as_deref() {
+                Some("gateway") => endpoints.external_gateway = Some(url),
+                _ => endpoints.external_rpc = Some(format!("{}{}", url, RPC_PATH)),
+            }
+        }
+
+        endpoints
+    }
+
+    fn exposure_metadata(&self, exposure: &DevnetExposure) -> meta::v1::ObjectMeta {
+        let mut metadata = self.object_metadata();
+        metadata.annotations = exposure.annotations.clone();
+        metadata
+    }
+
+    fn ingress_manifest(&self, exposure: &DevnetExposure) -> api::networking::v1::Ingress {
+        use api::networking::v1::{
+            HTTPIngressPath, HTTPIngressRuleValue, Ingress, IngressBackend, IngressRule,
+            IngressServiceBackend, IngressSpec, IngressTLS, ServiceBackendPort,
+        };
+        let ns = self.namespace().expect("devnet is namespaced");
+        let host = exposure.host_for(&self.name_any(), &ns);
+        let port = exposure.port.clone().unwrap_or_else(|| "rpc".to_string());
+
+        let tls = exposure.tls.as_ref().map(|tls| {
+            let secret_name = tls
+                .secret_name
+                .clone()
+                .unwrap_or_else(|| format!("{}-tls", self.name_any()));
+            vec![IngressTLS {
+                hosts: Some(vec![host.clone()]),
+                secret_name: Some(secret_name),
+            }]
+        });
+
+        let spec = IngressSpec {
+            ingress_class_name: exposure.ingress_class_name.clone(),
+            rules: Some(vec![IngressRule {
+                host: Some(host),
+                http: Some(HTTPIngressRuleValue {
+                    paths: vec![HTTPIngressPath {
+                        path: Some("/".to_string()),
+                        path_type: "Prefix".to_string(),
+                        backend: IngressBackend {
+                            service: Some(IngressServiceBackend {
+                                name: self.name_any(),
+                                port: Some(ServiceBackendPort {
+                                    name: Some(port),
+                                    ..ServiceBackendPort::default()
+                                }),
+                            }),
+                            ..IngressBackend::default()
+                        },
+                    }],
+                }),
+            }]),
+            tls,
+            ..IngressSpec::default()
+        };
+
+        Ingress {
+            metadata: self.exposure_metadata(exposure),
+            spec: Some(spec),
+            ..Ingress::default()
         }
     }
+
+    fn http_route_manifest(&self, exposure: &DevnetExposure) -> Result<DynamicObject> {
+        let ns = self.namespace().expect("devnet is namespaced");
+        let gateway_name = exposure.gateway_name.clone().ok_or_else(|| {
+            Error::InvalidSpec("exposure.gateway_name is required in HttpRoute mode".to_string())
+        })?;
+        let gateway_namespace = exposure
+            .gateway_namespace
+            .clone()
+            .unwrap_or_else(|| ns.clone());
+        let host = exposure.host_for(&self.name_any(), &ns);
+        let port = match exposure.port.as_deref() {
+            Some("gateway") => GATEWAY_PORT,
+            _ => RPC_PORT,
+        };
+
+        let mut route = DynamicObject::new(&self.name_any(), &http_route_api_resource());
+        route.metadata = self.exposure_metadata(exposure);
+        route.data = json!({
+            "spec": {
+                "parentRefs": [{
+                    "name": gateway_name,
+                    "namespace": gateway_namespace,
+                }],
+                "hostnames": [host],
+                "rules": [{
+                    "backendRefs": [{
+                        "name": self.name_any(),
+                        "port": port,
+                    }],
+                }],
+            },
+        });
+
+        Ok(route)
+    }
+}
+
+/// Mirror the pod `Ready` condition, using the container waiting reason when not ready.
+fn pod_ready_condition(pod: &api::core::v1::Pod) -> DevnetCondition {
+    let status = pod.status.clone().unwrap_or_default();
+    let ready = status
+        .conditions
+        .iter()
+        .flatten()
+        .any(|c| c.type_ == "Ready" && c.status == "True");
+
+    if ready {
+        return DevnetCondition::new("Ready", true, "PodReady", None);
+    }
+
+    let waiting = status
+        .container_statuses
+        .iter()
+        .flatten()
+        .find_map(|c| c.state.as_ref().and_then(|s| s.waiting.clone()));
+
+    match waiting {
+        Some(waiting) => DevnetCondition::new(
+            "Ready",
+            false,
+            waiting.reason.as_deref().unwrap_or("ContainerWaiting"),
+            waiting.message,
+        ),
+        None => DevnetCondition::new(
+            "Ready",
+            false,
+            "PodNotReady",
+            status.phase.map(|phase| format!("pod is {}", phase)),
+        ),
+    }
+}
+
+/// Derive the devnet state from its pod.
+///
+/// Pods being deleted or still pending are `Created`, pods that terminated or whose container
+/// can't start are `Errored`.
+fn pod_state(pod: &api::core::v1::Pod) -> DevnetState {
+    if pod.metadata.deletion_timestamp.is_some() {
+        return DevnetState::Created;
+    }
+
+    let status = pod.status.clone().unwrap_or_default();
+    let failing = status
+        .container_statuses
+        .iter()
+        .flatten()
+        .filter_map(|c| c.state.as_ref()?.waiting.as_ref()?.reason.as_deref())
+        .any(|reason| FAILED_WAITING_REASONS.contains(&reason));
+
+    match status.phase.as_deref() {
+        Some("Failed" | "Succeeded") => DevnetState::Errored,
+        _ if failing => DevnetState::Errored,
+        Some("Running") => DevnetState::Running,
+        _ => DevnetState::Created,
+    }
+}
+
+/// Returns a short hash of the value, stable across operator versions.
+fn short_hash(value: &str) -> String {
+    // FNV-1a
+    let hash = value.bytes().fold(0x811c9dc5u32, |hash, byte| {
+        (hash ^ byte as u32).wrapping_mul(0x01000193)
+    });
+    format!("{:08x}", hash)
+}
+
+fn http_route_api_resource() -> ApiResource {
+    let gvk = GroupVersionKind::gvk("gateway.networking.k8s.io", "v1beta1", "HTTPRoute");
+    ApiResource::from_gvk(&gvk)
 }
 
 fn error_policy(_devnet: Arc<Devnet>, error: &Error, _ctx: Arc<Context>) -> Action {
@@ -334,7 +1145,7 @@ fn error_policy(_devnet: Arc<Devnet>, error: &Error, _ctx: Arc<Context>) -> Acti
     Action::requeue(Duration::from_secs(10))
 }
 
-/// Start the controller.
+/// Start the controllers.
 pub async fn init(client: Client) -> Result<BoxFuture<'static, ()>> {
     let devnets = Api::<Devnet>::all(client.clone());
 
@@ -348,15 +1159,363 @@ pub async fn init(client: Client) -> Result<BoxFuture<'static, ()>> {
 
     let pods = Api::<api::core::v1::Pod>::all(client.clone());
     let services = Api::<api::core::v1::Service>::all(client.clone());
+    let ingresses = Api::<api::networking::v1::Ingress>::all(client.clone());
+    let claims = Api::<api::core::v1::PersistentVolumeClaim>::all(client.clone());
+    let jobs = Api::<api::batch::v1::Job>::all(client.clone());
+    let pools = Api::<DevnetPool>::all(client.clone());
+    let devnet_claims = Api::<DevnetClaim>::all(client.clone());
 
-    let ctx = Context { client };
-    let controller = Controller::new(devnets, ListParams::default())
+    let reporter = Reporter {
+        controller: "ryogoku".to_string(),
+        instance: std::env::var("POD_NAME").ok(),
+    };
+    let mut devnet_controller = Controller::new(devnets.clone(), ListParams::default())
         .owns(pods, ListParams::default())
         .owns(services, ListParams::default())
-        .run(reconcile_devnet, error_policy, ctx.into())
+        .owns(ingresses, ListParams::default())
+        .owns(claims, ListParams::default())
+        .owns(jobs, ListParams::default());
+
+    // devnets are reconciled again when their template changes.
+    let store = devnet_controller.store();
+    devnet_controller = devnet_controller.watches(
+        Api::<DevnetTemplate>::all(client.clone()),
+        ListParams::default(),
+        move |template| {
+            let ns = template.namespace();
+            template_devnets(
+                &store,
+                DevnetTemplateKind::DevnetTemplate,
+                ns.as_deref(),
+                &template.name_any(),
+            )
+        },
+    );
+    let store = devnet_controller.store();
+    devnet_controller = devnet_controller.watches(
+        Api::<ClusterDevnetTemplate>::all(client.clone()),
+        ListParams::default(),
+        move |template| {
+            template_devnets(
+                &store,
+                DevnetTemplateKind::ClusterDevnetTemplate,
+                None,
+                &template.name_any(),
+            )
+        },
+    );
+
+    // the Gateway API is optional, only watch routes when its CRDs are installed.
+    let route_resource = http_route_api_resource();
+    let routes = Api::<DynamicObject>::all_with(client.clone(), &route_resource);
+    if routes.list(&ListParams::default().limit(1)).await.is_ok() {
+        devnet_controller =
+            devnet_controller.owns_with(routes, route_resource, ListParams::default());
+    } else {
+        info!("HTTPRoute CRD is not installed, not watching routes");
+    }
+
+    let ctx = Arc::new(Context { client, reporter });
+    let devnet_controller = devnet_controller
+        .run(reconcile_devnet, error_policy, ctx.clone())
         .filter_map(|x| async move { std::result::Result::ok(x) })
-        .for_each(|_| futures::future::ready(()))
+        .for_each(|_| futures::future::ready(()));
+
+    // claimed devnets are no longer owned by their pool, watch them by label instead.
+    let pool_controller = Controller::new(pools, ListParams::default())
+        .watches(
+            devnets.clone(),
+            ListParams::default().labels(POOL_LABEL),
+            |devnet| {
+                let ns = devnet.namespace()?;
+                let pool = devnet.labels().get(POOL_LABEL)?;
+                Some(ObjectRef::new(pool).within(&ns))
+            },
+        )
+        .run(pool::reconcile_pool, pool::error_policy, ctx.clone())
+        .filter_map(|x| async move { std::result::Result::ok(x) })
+        .for_each(|_| futures::future::ready(()));
+
+    let claim_controller = Controller::new(devnet_claims, ListParams::default())
+        .owns(devnets, ListParams::default())
+        .run(claim::reconcile_claim, claim::error_policy, ctx)
+        .filter_map(|x| async move { std::result::Result::ok(x) })
+        .for_each(|_| futures::future::ready(()));
+
+    let controller = futures::future::join3(devnet_controller, pool_controller, claim_controller)
+        .map(|_| ())
         .boxed();
 
     Ok(controller)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use api::core::v1::{
+        ContainerState, ContainerStateWaiting, ContainerStatus, LoadBalancerIngress,
+        LoadBalancerStatus, Pod, PodCondition, PodStatus, Service, ServiceSpec, ServiceStatus,
+    };
+
+    use crate::devnet::{DevnetSpec, ExposureTls};
+
+    fn devnet(spec: DevnetSpec) -> Devnet {
+        let mut devnet = Devnet::new("my-devnet", spec);
+        devnet.metadata.namespace = Some("dev".to_string());
+        devnet
+    }
+
+    fn service(type_: &str, status: Option<ServiceStatus>) -> Service {
+        let ports = ["rpc", "gateway"]
+            .into_iter()
+            .zip([30001, 30002])
+            .map(|(name, node_port)| ServicePort {
+                name: Some(name.to_string()),
+                node_port: Some(node_port),
+                ..ServicePort::default()
+            })
+            .collect();
+        Service {
+            spec: Some(ServiceSpec {
+                type_: Some(type_.to_string()),
+                ports: Some(ports),
+                ..ServiceSpec::default()
+            }),
+            status,
+            ..Service::default()
+        }
+    }
+
+    #[test]
+    fn endpoints_include_the_rpc_path() {
+        let devnet = devnet(DevnetSpec::default());
+        let endpoints = devnet.endpoints(&Pod::default(), &service("ClusterIP", None));
+
+        assert_eq!(
+            endpoints.rpc,
+            "http://my-devnet.dev.svc.cluster.local:9575/rpc"
+        );
+        assert_eq!(
+            endpoints.gateway,
+            "http://my-devnet.dev.svc.cluster.local:5050"
+        );
+        assert_eq!(endpoints.external_rpc, None);
+        assert_eq!(endpoints.external_gateway, None);
+    }
+
+    #[test]
+    fn endpoints_use_the_load_balancer_address() {
+        let devnet = devnet(DevnetSpec::default());
+        let status = ServiceStatus {
+            load_balancer: Some(LoadBalancerStatus {
+                ingress: Some(vec![LoadBalancerIngress {
+                    ip: Some("10.0.0.1".to_string()),
+                    ..LoadBalancerIngress::default()
+                }]),
+            }),
+            ..ServiceStatus::default()
+        };
+        let endpoints = devnet.endpoints(&Pod::default(), &service("LoadBalancer", Some(status)));
+
+        assert_eq!(
+            endpoints.external_rpc.as_deref(),
+            Some("http://10.0.0.1:9575/rpc")
+        );
+        assert_eq!(
+            endpoints.external_gateway.as_deref(),
+            Some("http://10.0.0.1:5050")
+        );
+    }
+
+    #[test]
+    fn endpoints_use_the_node_ports() {
+        let devnet = devnet(DevnetSpec::default());
+        let pod = Pod {
+            status: Some(PodStatus {
+                host_ip: Some("192.168.0.2".to_string()),
+                ..PodStatus::default()
+            }),
+            ..Pod::default()
+        };
+        let endpoints = devnet.endpoints(&pod, &service("NodePort", None));
+
+        assert_eq!(
+            endpoints.external_rpc.as_deref(),
+            Some("http://192.168.0.2:30001/rpc")
+        );
+        assert_eq!(
+            endpoints.external_gateway.as_deref(),
+            Some("http://192.168.0.2:30002")
+        );
+    }
+
+    #[test]
+    fn endpoints_prefer_the_exposure_url() {
+        let devnet = devnet(DevnetSpec {
+            exposure: Some(DevnetExposure {
+                host: "{name}.{namespace}.example.com".to_string(),
+                tls: Some(ExposureTls::default()),
+                ..DevnetExposure::default()
+            }),
+            ..DevnetSpec::default()
+        });
+        let endpoints = devnet.endpoints(&Pod::default(), &service("ClusterIP", None));
+
+        assert_eq!(
+            endpoints.external_rpc.as_deref(),
+            Some("https://my-devnet.dev.example.com/rpc")
+        );
+        assert_eq!(endpoints.external_gateway, None);
+    }
+
+    fn pod(phase: &str, waiting_reason: Option<&str>) -> Pod {
+        let container_status = waiting_reason.map(|reason| ContainerStatus {
+            name: "starknet-devnet".to_string(),
+            state: Some(ContainerState {
+                waiting: Some(ContainerStateWaiting {
+                    reason: Some(reason.to_string()),
+                    message: None,
+                }),
+                ..ContainerState::default()
+            }),
+            ..ContainerStatus::default()
+        });
+
+        Pod {
+            status: Some(PodStatus {
+                phase: Some(phase.to_string()),
+                container_statuses: container_status.map(|status| vec![status]),
+                ..PodStatus::default()
+            }),
+            ..Pod::default()
+        }
+    }
+
+    #[test]
+    fn pod_state_follows_the_pod_phase() {
+        assert_eq!(pod_state(&Pod::default()), DevnetState::Created);
+        assert_eq!(pod_state(&pod("Pending", None)), DevnetState::Created);
+        assert_eq!(pod_state(&pod("Running", None)), DevnetState::Running);
+        assert_eq!(pod_state(&pod("Failed", None)), DevnetState::Errored);
+        assert_eq!(pod_state(&pod("Succeeded", None)), DevnetState::Errored);
+    }
+
+    #[test]
+    fn pod_state_is_errored_when_the_container_cant_start() {
+        assert_eq!(
+            pod_state(&pod("Pending", Some("ContainerCreating"))),
+            DevnetState::Created
+        );
+        assert_eq!(
+            pod_state(&pod("Pending", Some("ImagePullBackOff"))),
+            DevnetState::Errored
+        );
+        assert_eq!(
+            pod_state(&pod("Running", Some("CrashLoopBackOff"))),
+            DevnetState::Errored
+        );
+    }
+
+    #[test]
+    fn pod_state_is_created_while_the_pod_terminates() {
+        let mut terminating = pod("Running", None);
+        terminating.metadata.deletion_timestamp = Some(Time(Utc::now()));
+        assert_eq!(pod_state(&terminating), DevnetState::Created);
+
+        terminating.status = pod("Failed", None).status;
+        assert_eq!(pod_state(&terminating), DevnetState::Created);
+    }
+
+    #[test]
+    fn pod_ready_condition_uses_the_waiting_reason() {
+        let mut ready = pod("Running", None);
+        ready.status.as_mut().unwrap().conditions = Some(vec![PodCondition {
+            type_: "Ready".to_string(),
+            status: "True".to_string(),
+            ..PodCondition::default()
+        }]);
+        assert!(pod_ready_condition(&ready).is_true());
+
+        let condition = pod_ready_condition(&pod("Pending", Some("ErrImagePull")));
+        assert!(!condition.is_true());
+        assert_eq!(condition.reason.as_deref(), Some("ErrImagePull"));
+    }
+
+    fn exposure(port: Option<&str>) -> DevnetExposure {
+        DevnetExposure {
+            host: "{name}.{namespace}.devnets.example.com".to_string(),
+            port: port.map(str::to_string),
+            ingress_class_name: Some("nginx".to_string()),
+            gateway_name: Some("public".to_string()),
+            annotations: Some(BTreeMap::from([(
+                "example.com/owner".to_string(),
+                "qa".to_string(),
+            )])),
+            ..DevnetExposure::default()
+        }
+    }
+
+    #[test]
+    fn ingress_manifest_routes_the_host_to_the_service() {
+        let mut devnet = devnet(DevnetSpec::default());
+        devnet.metadata.uid = Some("uid".to_string());
+        let ingress = devnet.ingress_manifest(&DevnetExposure {
+            tls: Some(ExposureTls::default()),
+            ..exposure(None)
+        });
+
+        assert_eq!(
+            ingress.metadata.annotations.unwrap()["example.com/owner"],
+            "qa"
+        );
+        let spec = ingress.spec.unwrap();
+        assert_eq!(spec.ingress_class_name.as_deref(), Some("nginx"));
+        let rule = &spec.rules.unwrap()[0];
+        assert_eq!(
+            rule.host.as_deref(),
+            Some("my-devnet.dev.devnets.example.com")
+        );
+        let backend = rule.http.as_ref().unwrap().paths[0]
+            .backend
+            .service
+            .clone()
+            .unwrap();
+        assert_eq!(backend.name, "my-devnet");
+        assert_eq!(backend.port.unwrap().name.as_deref(), Some("rpc"));
+        let tls = &spec.tls.unwrap()[0];
+        assert_eq!(tls.secret_name.as_deref(), Some("my-devnet-tls"));
+        assert_eq!(
+            tls.hosts.as_deref(),
+            Some(&["my-devnet.dev.devnets.example.com".to_string()][..])
+        );
+    }
+
+    #[test]
+    fn http_route_manifest_attaches_to_the_gateway() {
+        let mut devnet = devnet(DevnetSpec::default());
+        devnet.metadata.uid = Some("uid".to_string());
+        let route = devnet
+            .http_route_manifest(&exposure(Some("gateway")))
+            .unwrap();
+
+        assert_eq!(route.types.unwrap().kind, "HTTPRoute");
+        assert_eq!(
+            route.data["spec"]["parentRefs"][0],
+            json!({ "name": "public", "namespace": "dev" })
+        );
+        assert_eq!(
+            route.data["spec"]["hostnames"],
+            json!(["my-devnet.dev.devnets.example.com"])
+        );
+        assert_eq!(
+            route.data["spec"]["rules"][0]["backendRefs"][0],
+            json!({ "name": "my-devnet", "port": GATEWAY_PORT })
+        );
+
+        let no_gateway = DevnetExposure {
+            gateway_name: None,
+            ..exposure(None)
+        };
+        assert!(devnet.http_route_manifest(&no_gateway).is_err());
+    }
+}
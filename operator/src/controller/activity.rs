@@ -0,0 +1,137 @@
+use std::sync::Arc;
+
+use k8s_openapi::{
+    api::core::v1::Pod,
+    apimachinery::pkg::apis::meta::v1::Time,
+    chrono::{DateTime, Utc},
+};
+use kube::{api::LogParams, Api, ResourceExt};
+use tracing::warn;
+
+use super::{Context, DEVNET_CONTAINER};
+use crate::{
+    devnet::{Devnet, DevnetStatus},
+    error::Result,
+    rpc::OPERATOR_QUERY,
+};
+
+impl Devnet {
+    /// Record the last time the devnet produced a block or served a request, used to compute
+    /// the idle time-to-live.
+    ///
+    /// Blocks are seen by the health check. Requests are read from the access log of the
+    /// devnet, ignoring the requests of the operator. An unreachable devnet keeps its last
+    /// activity.
+    pub(super) async fn observe_activity(
+        &self,
+        ctx: Arc<Context>,
+        pod: &Pod,
+        status: &mut DevnetStatus,
+    ) {
+        let block_number = status
+            .health
+            .as_ref()
+            .and_then(|health| health.block_number);
+        if block_number.is_some() && status.last_block_number != block_number {
+            status.last_block_number = block_number;
+            status.last_activity_time = Some(Time(Utc::now()));
+        }
+
+        match self
+            .last_request_time(ctx, pod, status.last_activity_time.as_ref())
+            .await
+        {
+            Ok(Some(requested)) => {
+                let active = status.last_activity_time.as_ref().map(|t| t.0);
+                if active.map(|active| active < requested).unwrap_or(true) {
+                    status.last_activity_time = Some(Time(requested));
+                }
+            }
+            Ok(None) => {}
+            Err(err) => {
+                warn!(
+                    devnet = self.name_any(),
+                    namespace = self.metadata.namespace,
+                    error = %err,
+                    "failed to read the devnet requests"
+                );
+            }
+        }
+    }
+
+    /// Returns the time of the last request served by the devnet since it was last active.
+    async fn last_request_time(
+        &self,
+        ctx: Arc<Context>,
+        pod: &Pod,
+        last_activity: Option<&Time>,
+    ) -> Result<Option<DateTime<Utc>>> {
+        let ns = self.namespace().expect("devnet is namespaced");
+        let pods: Api<Pod> = Api::namespaced(ctx.client.clone(), &ns);
+
+        // requests older than the idle time-to-live don't delay the expiry.
+        let ttl = self
+            .spec
+            .ttl_seconds_after_idle
+            .and_then(|ttl| i64::try_from(ttl).ok())
+            .unwrap_or(i64::MAX);
+        let since_seconds = last_activity
+            .or(self.metadata.creation_timestamp.as_ref())
+            .map(|active| (Utc::now() - active.0).num_seconds())
+            .unwrap_or(ttl)
+            .clamp(1, ttl.max(1));
+
+        let lp = LogParams {
+            container: Some(DEVNET_CONTAINER.to_string()),
+            since_seconds: Some(since_seconds),
+            timestamps: true,
+            ..LogParams::default()
+        };
+        let logs = pods.logs(&pod.name_any(), &lp).await?;
+        Ok(last_request_time(&logs))
+    }
+}
+
+/// Returns the time of the last request in the timestamped devnet log, ignoring the requests of
+/// the operator.
+fn last_request_time(logs: &str) -> Option<DateTime<Utc>> {
+    logs.lines()
+        .filter(|line| is_request(line))
+        .filter_map(|line| {
+            let (timestamp, _) = line.split_once(' ')?;
+            DateTime::parse_from_rfc3339(timestamp).ok()
+        })
+        .map(|time| time.with_timezone(&Utc))
+        .max()
+}
+
+/// Returns true for access log lines, which quote the request line like `"POST /rpc HTTP/1.1"`.
+fn is_request(line: &str) -> bool {
+    let request = ["\"GET /", "\"POST /"]
+        .iter()
+        .any(|method| line.contains(method));
+    request && !line.contains(OPERATOR_QUERY)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn last_request_time_ignores_the_operator() {
+        let logs = r#"2023-03-01T10:00:00.000000000Z Account #0
+2023-03-01T10:01:00.000000000Z 10.0.0.5 - - [01/Mar/2023 10:01:00] "POST /rpc HTTP/1.1" 200 -
+2023-03-01T10:02:00.000000000Z 10.0.0.1 - - [01/Mar/2023 10:02:00] "POST /rpc?source=ryogoku-operator HTTP/1.1" 200 -
+2023-03-01T10:03:00.000000000Z Block 2 produced
+"#;
+        assert_eq!(
+            last_request_time(logs).map(|t| t.to_rfc3339()).as_deref(),
+            Some("2023-03-01T10:01:00+00:00")
+        );
+
+        let operator_only = r#"2023-03-01T10:02:00.000000000Z 10.0.0.1 - - [01/Mar/2023 10:02:00] "GET /predeployed_accounts?source=ryogoku-operator HTTP/1.1" 200 -
+"#;
+        assert_eq!(last_request_time(operator_only), None);
+        assert_eq!(last_request_time(""), None);
+    }
+}
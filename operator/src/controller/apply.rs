@@ -0,0 +1,364 @@
+use std::{fmt::Debug, sync::Arc};
+
+use kube::{
+    api::{DeleteParams, Patch, PatchParams},
+    runtime::events::{Event, EventType, Recorder},
+    Api, Resource, ResourceExt,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::json;
+use tracing::{debug, info, warn};
+
+use super::{short_hash, Context};
+use crate::{devnet::Devnet, error::Result};
+
+/// Field manager of the objects applied by the operator.
+static FIELD_MANAGER: &str = "ryogoku";
+/// Annotation recording the hash of the last rejected change of an object, so that the
+/// rejection is only reported once.
+static REJECTED_ANNOTATION: &str = "ryogoku.stark/rejected-apply";
+
+impl Devnet {
+    /// Server-side apply an object owned by the devnet.
+    ///
+    /// Changes made to the fields managed by the operator are reverted and reported as
+    /// `DriftCorrected` events. Conflicts with other field managers are reported as
+    /// `ApplyConflict` events before taking the fields over. Changes the api server
+    /// rejects, like the immutable fields of a running pod, keep the existing object and are
+    /// reported once as `ApplyRejected` events.
+    pub(super) async fn apply_owned<K>(
+        &self,
+        ctx: Arc<Context>,
+        api: &Api<K>,
+        desired: &K,
+    ) -> Result<K>
+    where
+        K: Resource<DynamicType = ()> + Clone + Debug + Serialize + DeserializeOwned,
+    {
+        self.apply_owned_with(ctx, api, desired, &()).await
+    }
+
+    /// Server-side apply an object owned by the devnet, for kinds known at runtime like the
+    /// Gateway API ones.
+    pub(super) async fn apply_owned_with<K>(
+        &self,
+        ctx: Arc<Context>,
+        api: &Api<K>,
+        desired: &K,
+        dyntype: &K::DynamicType,
+    ) -> Result<K>
+    where
+        K: Resource + Clone + Debug + Serialize + DeserializeOwned,
+    {
+        let name = desired.name_any();
+        let kind = K::kind(dyntype);
+        let existing = api.get_opt(&name).await?;
+        if let Some(existing) = existing
+            .as_ref()
+            .filter(|e| e.meta().deletion_timestamp.is_some())
+        {
+            // the object is replaced once it's gone, applying changes now would be rejected.
+            debug!(
+                kind = %kind,
+                name = %name,
+                namespace = self.metadata.namespace,
+                "object is being deleted, not applying"
+            );
+            return Ok(existing.clone());
+        }
+
+        let pp = PatchParams::apply(FIELD_MANAGER);
+        let applied = match api.patch(&name, &pp, &Patch::Apply(desired)).await {
+            Ok(applied) => applied,
+            Err(kube::Error::Api(err)) if err.code == 409 => {
+                warn!(
+                    kind = %kind,
+                    name = %name,
+                    namespace = self.metadata.namespace,
+                    message = %err.message,
+                    "apply conflict, forcing"
+                );
+                self.publish_event(
+                    ctx.clone(),
+                    EventType::Warning,
+                    "ApplyConflict",
+                    format!("{} {}: {}", kind, name, err.message),
+                )
+                .await;
+                api.patch(&name, &pp.clone().force(), &Patch::Apply(desired))
+                    .await?
+            }
+            Err(kube::Error::Api(err)) if err.code == 422 && existing.is_some() => {
+                let existing = existing.expect("existing object");
+                let hash = change_hash(desired);
+                if rejection_reported(&existing, &hash) {
+                    debug!(
+                        kind = %kind,
+                        name = %name,
+                        namespace = self.metadata.namespace,
+                        "apply rejected, already reported"
+                    );
+                    return Ok(existing);
+                }
+
+                warn!(
+                    kind = %kind,
+                    name = %name,
+                    namespace = self.metadata.namespace,
+                    message = %err.message,
+                    "apply rejected, keeping existing object"
+                );
+                self.publish_event(
+                    ctx.clone(),
+                    EventType::Warning,
+                    "ApplyRejected",
+                    format!(
+                        "{} {} was not updated, restart the devnet to apply the change: {}",
+                        kind, name, err.message
+                    ),
+                )
+                .await;
+                return self.annotate_rejected(api, &name, Some(&hash)).await;
+            }
+            Err(err) => return Err(err.into()),
+        };
+
+        // a later rejection of the same change is reported again.
+        let applied = if applied.annotations().contains_key(REJECTED_ANNOTATION) {
+            self.annotate_rejected(api, &name, None).await?
+        } else {
+            applied
+        };
+
+        match existing {
+            None => {
+                info!(
+                    kind = %kind,
+                    name = %name,
+                    namespace = self.metadata.namespace,
+                    "object created"
+                );
+            }
+            Some(existing) if desired_fields(&existing) != desired_fields(&applied) => {
+                info!(
+                    kind = %kind,
+                    name = %name,
+                    namespace = self.metadata.namespace,
+                    "object drift corrected"
+                );
+                self.publish_event(
+                    ctx.clone(),
+                    EventType::Normal,
+                    "DriftCorrected",
+                    format!(
+                        "{} {} did not match the devnet spec and was updated",
+                        kind, name
+                    ),
+                )
+                .await;
+            }
+            Some(_) => {
+                debug!(
+                    kind = %kind,
+                    name = %name,
+                    namespace = self.metadata.namespace,
+                    "object up to date"
+                );
+            }
+        }
+
+        Ok(applied)
+    }
+
+    /// Set or remove the rejected change annotation of the object.
+    async fn annotate_rejected<K>(&self, api: &Api<K>, name: &str, hash: Option<&str>) -> Result<K>
+    where
+        K: Resource + Clone + Debug + DeserializeOwned,
+    {
+        let pp = PatchParams {
+            field_manager: Some(FIELD_MANAGER.to_string()),
+            ..PatchParams::default()
+        };
+        Ok(api
+            .patch(name, &pp, &Patch::Merge(&rejected_patch(hash)))
+            .await?)
+    }
+
+    /// Delete the object with the given name, if the devnet owns it.
+    pub(super) async fn delete_owned<K>(&self, api: &Api<K>, name: &str) -> Result<()>
+    where
+        K: Resource + Clone + Debug + DeserializeOwned,
+    {
+        let existing = match api.get_opt(name).await? {
+            Some(existing) => existing,
+            None => return Ok(()),
+        };
+        let owned = existing
+            .owner_references()
+            .iter()
+            .any(|owner| Some(&owner.uid) == self.metadata.uid.as_ref());
+        if !owned || existing.meta().deletion_timestamp.is_some() {
+            return Ok(());
+        }
+
+        api.delete(&existing.name_any(), &DeleteParams::default())
+            .await?;
+        info!(
+            name = existing.name_any(),
+            namespace = self.metadata.namespace,
+            "owned object deleted"
+        );
+        Ok(())
+    }
+
+    /// Publish an event about the devnet. Errors are logged and ignored.
+    pub(super) async fn publish_event(
+        &self,
+        ctx: Arc<Context>,
+        type_: EventType,
+        reason: &str,
+        note: String,
+    ) {
+        let recorder = Recorder::new(
+            ctx.client.clone(),
+            ctx.reporter.clone(),
+            self.object_ref(&()),
+        );
+        let event = Event {
+            type_,
+            reason: reason.to_string(),
+            note: Some(note),
+            action: "Reconcile".to_string(),
+            secondary: None,
+        };
+        if let Err(err) = recorder.publish(event).await {
+            warn!(
+                devnet = self.name_any(),
+                namespace = self.metadata.namespace,
+                error = %err,
+                "failed to publish event"
+            );
+        }
+    }
+}
+
+/// Returns the object without its status, the metadata maintained by the api server and the
+/// rejected change annotation.
+fn desired_fields<K: Serialize>(object: &K) -> serde_json::Value {
+    let mut value = serde_json::to_value(object).unwrap_or_default();
+    if let Some(object) = value.as_object_mut() {
+        object.remove("status");
+    }
+    if let Some(metadata) = value["metadata"].as_object_mut() {
+        for field in ["resourceVersion", "managedFields", "generation"] {
+            metadata.remove(field);
+        }
+        if let Some(annotations) = metadata
+            .get_mut("annotations")
+            .and_then(|a| a.as_object_mut())
+        {
+            annotations.remove(REJECTED_ANNOTATION);
+            // the api server drops empty annotations.
+            if annotations.is_empty() {
+                metadata.remove("annotations");
+            }
+        }
+    }
+    value
+}
+
+/// Returns the hash of the desired object recorded in the rejected change annotation.
+fn change_hash<K: Serialize>(desired: &K) -> String {
+    short_hash(&serde_json::to_string(desired).unwrap_or_default())
+}
+
+/// Returns true if the rejection of the change with the given hash was already reported.
+fn rejection_reported<K: Resource>(existing: &K, hash: &str) -> bool {
+    existing
+        .annotations()
+        .get(REJECTED_ANNOTATION)
+        .map(String::as_str)
+        == Some(hash)
+}
+
+/// Merge patch setting the rejected change annotation, or removing it without a hash.
+fn rejected_patch(hash: Option<&str>) -> serde_json::Value {
+    json!({ "metadata": { "annotations": { REJECTED_ANNOTATION: hash } } })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use k8s_openapi::api::core::v1::{ConfigMap, Pod, PodSpec, PodStatus};
+    use kube::api::ObjectMeta;
+
+    use super::*;
+
+    fn pod(annotations: Option<BTreeMap<String, String>>) -> Pod {
+        Pod {
+            metadata: ObjectMeta {
+                name: Some("my-devnet".to_string()),
+                annotations,
+                ..ObjectMeta::default()
+            },
+            spec: Some(PodSpec::default()),
+            status: None,
+        }
+    }
+
+    #[test]
+    fn desired_fields_ignore_server_fields_and_the_rejected_annotation() {
+        let desired = pod(None);
+        let mut existing = pod(Some(BTreeMap::from([(
+            REJECTED_ANNOTATION.to_string(),
+            "0badcafe".to_string(),
+        )])));
+        existing.metadata.resource_version = Some("42".to_string());
+        existing.metadata.generation = Some(3);
+        existing.metadata.managed_fields = Some(Vec::new());
+        existing.status = Some(PodStatus {
+            phase: Some("Running".to_string()),
+            ..PodStatus::default()
+        });
+        assert_eq!(desired_fields(&existing), desired_fields(&desired));
+
+        let drifted = pod(Some(BTreeMap::from([(
+            "other".to_string(),
+            "value".to_string(),
+        )])));
+        assert_ne!(desired_fields(&drifted), desired_fields(&desired));
+    }
+
+    #[test]
+    fn rejected_changes_are_reported_once() {
+        let desired = ConfigMap {
+            data: Some(BTreeMap::from([("key".to_string(), "value".to_string())])),
+            ..ConfigMap::default()
+        };
+        let hash = change_hash(&desired);
+        assert_eq!(hash, change_hash(&desired.clone()));
+        assert_ne!(hash, change_hash(&ConfigMap::default()));
+
+        assert!(!rejection_reported(&pod(None), &hash));
+        let annotated = pod(Some(BTreeMap::from([(
+            REJECTED_ANNOTATION.to_string(),
+            hash.clone(),
+        )])));
+        assert!(rejection_reported(&annotated, &hash));
+        assert!(!rejection_reported(&annotated, "0badcafe"));
+    }
+
+    #[test]
+    fn rejected_patch_sets_or_removes_the_annotation() {
+        assert_eq!(
+            rejected_patch(Some("0badcafe"))["metadata"]["annotations"][REJECTED_ANNOTATION],
+            "0badcafe"
+        );
+        assert!(rejected_patch(None)["metadata"]["annotations"][REJECTED_ANNOTATION].is_null());
+        assert!(rejected_patch(None)["metadata"]["annotations"]
+            .as_object()
+            .unwrap()
+            .contains_key(REJECTED_ANNOTATION));
+    }
+}
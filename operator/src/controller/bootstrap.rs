@@ -0,0 +1,748 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use k8s_openapi::{
+    api::{
+        batch::v1::{Job, JobSpec},
+        core::v1::{
+            ConfigMapVolumeSource, Container, EmptyDirVolumeSource, EnvVar, EnvVarSource, Pod,
+            PodSpec, PodTemplateSpec, Secret, SecretKeySelector, Volume, VolumeMount,
+        },
+    },
+    ByteString,
+};
+use kube::{
+    api::{ListParams, PostParams},
+    runtime::{controller::Action, events::EventType},
+    Api, ResourceExt,
+};
+use serde::Deserialize;
+use tracing::{info, warn};
+
+use super::{
+    pod_ready_condition, short_hash, Context, DEFAULT_IMAGE, GATEWAY_PORT, RPC_PATH, RPC_PORT,
+};
+use crate::{
+    bootstrap::{
+        BootstrapContracts, BootstrapPhase, BootstrapResults, BootstrapStep, BootstrapStepStatus,
+    },
+    devnet::{Devnet, DevnetCondition, DevnetStatus},
+    dojo::DOJO_STEP,
+    error::Result,
+    rpc::DevnetClient,
+    Error,
+};
+
+/// Mount path of the contract artifacts in built-in steps.
+static ARTIFACTS_PATH: &str = "/artifacts";
+/// Finished bootstrap jobs are deleted after an hour.
+const JOB_TTL_SECONDS: i32 = 60 * 60;
+/// Maximum length of job names, which are used as label values.
+const MAX_JOB_NAME_LENGTH: usize = 63;
+/// Key of the account private key in the bootstrap Secret.
+static PRIVATE_KEY_KEY: &str = "private_key";
+
+/// Pre-deployed account, as returned by the devnet.
+#[derive(Deserialize)]
+struct PredeployedAccount {
+    address: String,
+    public_key: String,
+    private_key: String,
+}
+
+impl Devnet {
+    /// Run the bootstrap steps in order once the pod is ready, recording their progress and
+    /// results in the status.
+    ///
+    /// Steps run again when the pod is recreated, unless the devnet restarts from a state dump.
+    pub(super) async fn bootstrap(
+        &self,
+        ctx: Arc<Context>,
+        pod: &Pod,
+        status: &mut DevnetStatus,
+    ) -> Result<()> {
+        let steps = self.bootstrap_steps();
+        if steps.is_empty() {
+            return Ok(());
+        }
+
+        let pod_uid = pod.uid().expect("pod has uid");
+        let mut bootstrap = status.bootstrap.clone().unwrap_or_default();
+        if bootstrap.pod_uid.as_deref() != Some(pod_uid.as_str()) {
+            if status.dump_path.is_some() {
+                // the contracts are restored with the state, retry failed steps only.
+                bootstrap
+                    .steps
+                    .retain(|step| step.phase == BootstrapPhase::Succeeded);
+            } else {
+                bootstrap.steps.clear();
+            }
+            bootstrap.pod_uid = Some(pod_uid.clone());
+        }
+
+        let started = !self.l1_pending(pod, status) && !self.startup_pending(pod, status);
+        if pod_ready_condition(pod).is_true() && started {
+            for step in &steps {
+                let index = match bootstrap.steps.iter().position(|s| s.name == step.name) {
+                    Some(index) => index,
+                    None => {
+                        bootstrap.steps.push(BootstrapStepStatus {
+                            name: step.name.clone(),
+                            ..BootstrapStepStatus::default()
+                        });
+                        bootstrap.steps.len() - 1
+                    }
+                };
+                let step_status = &mut bootstrap.steps[index];
+
+                match step_status.phase {
+                    BootstrapPhase::Succeeded => continue,
+                    BootstrapPhase::Failed => break,
+                    BootstrapPhase::Pending | BootstrapPhase::Running => {
+                        let job = self
+                            .setup_bootstrap_job(ctx.clone(), step, &pod_uid)
+                            .await?;
+                        self.observe_bootstrap_job(ctx.clone(), &job, step_status)
+                            .await?;
+                        if step_status.phase != BootstrapPhase::Succeeded {
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+
+        status.bootstrap = Some(bootstrap);
+        Ok(())
+    }
+
+    /// Check the bootstrap steps before running them, they are only validated by the schema.
+    pub(super) fn validate_bootstrap(&self) -> Result<()> {
+        validate_steps(self.spec.bootstrap.as_deref().unwrap_or_default())
+    }
+
+    /// Report invalid bootstrap steps in the `Ready` condition, until the spec changes.
+    pub(super) async fn invalid_bootstrap(
+        &self,
+        ctx: Arc<Context>,
+        mut status: DevnetStatus,
+        message: String,
+    ) -> Result<Action> {
+        warn!(
+            devnet = self.name_any(),
+            namespace = self.metadata.namespace,
+            error = %message,
+            "invalid bootstrap steps"
+        );
+        self.publish_event(
+            ctx.clone(),
+            EventType::Warning,
+            "InvalidBootstrap",
+            message.clone(),
+        )
+        .await;
+
+        status.set_condition(DevnetCondition::new(
+            "Ready",
+            false,
+            "InvalidBootstrap",
+            Some(message),
+        ));
+        self.update_status(ctx, status).await?;
+        Ok(Action::await_change())
+    }
+
+    /// Returns the `Ready` condition of the devnet: its pod is ready and it is bootstrapped.
+    pub(super) fn ready_condition(&self, pod: &Pod, status: &DevnetStatus) -> DevnetCondition {
+        let condition = pod_ready_condition(pod);
+        if !condition.is_true() {
+            return condition;
+        }
+        if let Some(condition) = self
+            .startup_condition(pod, status)
+            .or_else(|| self.l1_condition(pod, status))
+        {
+            return condition;
+        }
+
+        let steps = self.bootstrap_steps();
+        if steps.is_empty() {
+            return condition;
+        }
+
+        let bootstrap = status
+            .bootstrap
+            .as_ref()
+            .filter(|bootstrap| bootstrap.pod_uid == pod.uid())
+            .cloned()
+            .unwrap_or_default();
+
+        for step in &steps {
+            let step_status = bootstrap.step(&step.name);
+            match step_status.map(|s| s.phase).unwrap_or_default() {
+                BootstrapPhase::Succeeded => continue,
+                BootstrapPhase::Failed => {
+                    let message = step_status.and_then(|s| s.message.clone());
+                    return DevnetCondition::new(
+                        "Ready",
+                        false,
+                        "BootstrapFailed",
+                        Some(format!(
+                            "bootstrap step {} failed: {}",
+                            step.name,
+                            message.as_deref().unwrap_or("unknown reason")
+                        )),
+                    );
+                }
+                BootstrapPhase::Pending | BootstrapPhase::Running => {
+                    return DevnetCondition::new(
+                        "Ready",
+                        false,
+                        "Bootstrapping",
+                        Some(format!("running bootstrap step {}", step.name)),
+                    );
+                }
+            }
+        }
+
+        condition
+    }
+
+    /// Create the job running the step against the given pod, if it doesn't exist.
+    async fn setup_bootstrap_job(
+        &self,
+        ctx: Arc<Context>,
+        step: &BootstrapStep,
+        pod_uid: &str,
+    ) -> Result<Job> {
+        let ns = self.namespace().expect("devnet is namespaced");
+        let jobs: Api<Job> = Api::namespaced(ctx.client.clone(), &ns);
+
+        let job_name = self.bootstrap_job_name(step, pod_uid);
+        if let Some(job) = jobs.get_opt(&job_name).await? {
+            return Ok(job);
+        }
+
+        let client = DevnetClient::for_operator(ctx.client.clone(), &ns, &self.name_any());
+        let accounts: Vec<PredeployedAccount> =
+            client.get("gateway", "predeployed_accounts").await?;
+        let account = accounts
+            .first()
+            .ok_or_else(|| Error::Rpc("devnet has no pre-deployed account".to_string()))?;
+        let chain_id = client.chain_id().await?;
+
+        let secrets: Api<Secret> = Api::namespaced(ctx.client.clone(), &ns);
+        self.apply_owned(
+            ctx.clone(),
+            &secrets,
+            &self.bootstrap_secret_manifest(account),
+        )
+        .await?;
+
+        let job_manifest = self.bootstrap_job_manifest(step, &job_name, account, &chain_id)?;
+        let job = jobs.create(&PostParams::default(), &job_manifest).await?;
+        info!(
+            job = job.name_any(),
+            step = %step.name,
+            namespace = job.metadata.namespace,
+            "bootstrap job created"
+        );
+        Ok(job)
+    }
+
+    /// Update the step status from its job, reading the results of successful jobs.
+    async fn observe_bootstrap_job(
+        &self,
+        ctx: Arc<Context>,
+        job: &Job,
+        step_status: &mut BootstrapStepStatus,
+    ) -> Result<()> {
+        let job_status = job.status.clone().unwrap_or_default();
+        step_status.job = Some(job.name_any());
+
+        let failed = job_status
+            .conditions
+            .iter()
+            .flatten()
+            .find(|c| c.type_ == "Failed" && c.status == "True");
+        if let Some(failed) = failed {
+            step_status.phase = BootstrapPhase::Failed;
+            step_status.message = failed.message.clone().or_else(|| failed.reason.clone());
+            return Ok(());
+        }
+
+        if job_status.succeeded.unwrap_or(0) == 0 {
+            step_status.phase = BootstrapPhase::Running;
+            return Ok(());
+        }
+
+        let ns = self.namespace().expect("devnet is namespaced");
+        let pods: Api<Pod> = Api::namespaced(ctx.client.clone(), &ns);
+        let lp = ListParams::default().labels(&format!("job-name={}", job.name_any()));
+        let message = pods
+            .list(&lp)
+            .await?
+            .into_iter()
+            .filter(|pod| pod.status.as_ref().and_then(|s| s.phase.as_deref()) == Some("Succeeded"))
+            .flat_map(|pod| {
+                pod.status
+                    .and_then(|s| s.container_statuses)
+                    .unwrap_or_default()
+            })
+            .find_map(|c| c.state.and_then(|s| s.terminated).and_then(|t| t.message));
+
+        if let Some(message) = message.filter(|m| !m.trim().is_empty()) {
+            match serde_json::from_str::<BootstrapResults>(&message) {
+                Ok(results) => {
+                    step_status.class_hashes = Some(results.class_hashes);
+                    step_status.addresses = Some(results.addresses);
+                }
+                Err(err) => {
+                    warn!(
+                        job = job.name_any(),
+                        error = %err,
+                        "invalid bootstrap results"
+                    );
+                }
+            }
+        }
+
+        step_status.phase = BootstrapPhase::Succeeded;
+        step_status.message = None;
+        info!(
+            job = job.name_any(),
+            step = %step_status.name,
+            namespace = job.metadata.namespace,
+            "bootstrap step succeeded"
+        );
+        Ok(())
+    }
+
+    /// Returns the name of the step job, hashing the end of names that are too long.
+    fn bootstrap_job_name(&self, step: &BootstrapStep, pod_uid: &str) -> String {
+        let suffix: String = pod_uid.chars().take(5).collect();
+        job_name(&format!("{}-{}-{}", self.name_any(), step.name, suffix))
+    }
+
+    fn bootstrap_secret_name(&self) -> String {
+        format!("{}-bootstrap", self.name_any())
+    }
+
+    /// Secret holding the private key of the account used by the steps.
+    fn bootstrap_secret_manifest(&self, account: &PredeployedAccount) -> Secret {
+        let mut metadata = self.object_metadata();
+        metadata.name = Some(self.bootstrap_secret_name());
+
+        Secret {
+            metadata,
+            data: Some(BTreeMap::from([(
+                PRIVATE_KEY_KEY.to_string(),
+                ByteString(account.private_key.clone().into_bytes()),
+            )])),
+            ..Secret::default()
+        }
+    }
+
+    fn bootstrap_job_manifest(
+        &self,
+        step: &BootstrapStep,
+        job_name: &str,
+        account: &PredeployedAccount,
+        chain_id: &str,
+    ) -> Result<Job> {
+        let ns = self.namespace().expect("devnet is namespaced");
+        let host = format!("{}.{}.svc.cluster.local", self.name_any(), ns);
+        let env = [
+            (
+                "STARKNET_RPC",
+                format!("http://{}:{}{}", host, RPC_PORT, RPC_PATH),
+            ),
+            (
+                "STARKNET_GATEWAY_URL",
+                format!("http://{}:{}", host, GATEWAY_PORT),
+            ),
+            ("STARKNET_ACCOUNT_ADDRESS", account.address.clone()),
+            ("STARKNET_PUBLIC_KEY", account.public_key.clone()),
+            ("STARKNET_CHAIN_ID", chain_id.to_string()),
+        ]
+        .into_iter()
+        .map(|(name, value)| EnvVar {
+            name: name.to_string(),
+            value: Some(value),
+            ..EnvVar::default()
+        })
+        .chain([EnvVar {
+            name: "STARKNET_PRIVATE_KEY".to_string(),
+            value_from: Some(EnvVarSource {
+                secret_key_ref: Some(SecretKeySelector {
+                    name: Some(self.bootstrap_secret_name()),
+                    key: PRIVATE_KEY_KEY.to_string(),
+                    ..SecretKeySelector::default()
+                }),
+                ..EnvVarSource::default()
+            }),
+            ..EnvVar::default()
+        }])
+        .collect();
+
+        let mut pod_spec = match (&step.image, &step.contracts) {
+            (Some(image), None) => PodSpec {
+                containers: vec![Container {
+                    name: "bootstrap".to_string(),
+                    image: Some(image.clone()),
+                    command: step.command.clone(),
+                    args: step.args.clone(),
+                    ..Container::default()
+                }],
+                ..PodSpec::default()
+            },
+            (None, Some(contracts)) => self.contracts_pod_spec(contracts)?,
+            (None, None) if step.name == DOJO_STEP && self.spec.dojo.is_some() => {
+                self.dojo_migrate_pod_spec(self.spec.dojo.as_ref().expect("dojo is set"))
+            }
+            _ => {
+                return Err(Error::InvalidSpec(format!(
+                    "bootstrap step {} needs exactly one of image or contracts",
+                    step.name
+                )))
+            }
+        };
+        pod_spec.restart_policy = Some("Never".to_string());
+        if let Some(container) = pod_spec.containers.first_mut() {
+            container.env = Some(env);
+        }
+
+        let mut metadata = self.object_metadata();
+        metadata.name = Some(job_name.to_string());
+        metadata.labels.get_or_insert_with(BTreeMap::new).insert(
+            "ryogoku.stark/bootstrap_step".to_string(),
+            step.name.clone(),
+        );
+
+        Ok(Job {
+            metadata,
+            spec: Some(JobSpec {
+                backoff_limit: Some(2),
+                ttl_seconds_after_finished: Some(JOB_TTL_SECONDS),
+                template: PodTemplateSpec {
+                    spec: Some(pod_spec),
+                    ..PodTemplateSpec::default()
+                },
+                ..JobSpec::default()
+            }),
+            status: None,
+        })
+    }
+
+    /// Pod declaring and deploying the contracts with the `starknet` cli of the devnet image.
+    fn contracts_pod_spec(&self, contracts: &BootstrapContracts) -> Result<PodSpec> {
+        let image = self
+            .spec
+            .image
+            .clone()
+            .unwrap_or_else(|| DEFAULT_IMAGE.to_string());
+
+        let source = match (&contracts.config_map, &contracts.image) {
+            (Some(config_map), None) => Volume {
+                name: "artifacts".to_string(),
+                config_map: Some(ConfigMapVolumeSource {
+                    name: Some(config_map.clone()),
+                    ..ConfigMapVolumeSource::default()
+                }),
+                ..Volume::default()
+            },
+            (None, Some(_)) => Volume {
+                name: "artifacts".to_string(),
+                empty_dir: Some(EmptyDirVolumeSource::default()),
+                ..Volume::default()
+            },
+            _ => {
+                return Err(Error::InvalidSpec(
+                    "bootstrap contracts need exactly one of config_map or image".to_string(),
+                ))
+            }
+        };
+        let mount = VolumeMount {
+            name: "artifacts".to_string(),
+            mount_path: ARTIFACTS_PATH.to_string(),
+            ..VolumeMount::default()
+        };
+
+        // copy the artifacts out of their image before the step runs.
+        let init_containers = contracts.image.as_ref().map(|artifacts_image| {
+            vec![Container {
+                name: "artifacts".to_string(),
+                image: Some(artifacts_image.clone()),
+                command: Some(vec![
+                    "cp".to_string(),
+                    "-r".to_string(),
+                    format!("{}/.", ARTIFACTS_PATH),
+                    "/out".to_string(),
+                ]),
+                volume_mounts: Some(vec![VolumeMount {
+                    name: "artifacts".to_string(),
+                    mount_path: "/out".to_string(),
+                    ..VolumeMount::default()
+                }]),
+                ..Container::default()
+            }]
+        });
+
+        Ok(PodSpec {
+            init_containers,
+            containers: vec![Container {
+                name: "bootstrap".to_string(),
+                image: Some(image),
+                command: Some(vec![
+                    "/bin/sh".to_string(),
+                    "-c".to_string(),
+                    contracts_script(contracts)?,
+                ]),
+                volume_mounts: Some(vec![mount]),
+                ..Container::default()
+            }],
+            volumes: Some(vec![source]),
+            ..PodSpec::default()
+        })
+    }
+}
+
+/// Shell script declaring and deploying the contracts, writing the results to the termination
+/// message.
+fn contracts_script(contracts: &BootstrapContracts) -> Result<String> {
+    let mut script = String::from(
+        r#"set -eu
+mkdir -p "$HOME/.starknet_accounts"
+cat > "$HOME/.starknet_accounts/starknet_open_zeppelin_accounts.json" <<EOF
+{"devnet": {"__default__": {"private_key": "$STARKNET_PRIVATE_KEY", "public_key": "$STARKNET_PUBLIC_KEY", "salt": "0x0", "address": "$STARKNET_ACCOUNT_ADDRESS", "deployed": true}}}
+EOF
+export STARKNET_WALLET=starkware.starknet.wallets.open_zeppelin.OpenZeppelinAccount
+gateway="--gateway_url $STARKNET_GATEWAY_URL --feeder_gateway_url $STARKNET_GATEWAY_URL --network_id devnet --chain_id $STARKNET_CHAIN_ID"
+class_hashes=""
+addresses=""
+"#,
+    );
+
+    let declare = contracts.declare.clone().unwrap_or_default();
+    for (index, class) in declare.iter().enumerate() {
+        let artifact = format!("{}/{}", ARTIFACTS_PATH, class.artifact);
+        script.push_str(&format!(
+            "class_{index}=$(starknet declare $gateway --contract {} | sed -n 's/^Contract class hash: //p')\ntest -n \"$class_{index}\"\n",
+            shell_quote(&artifact),
+        ));
+        script.push_str(&format!(
+            "class_hashes=\"$class_hashes,$(printf '%s:\"%s\"' {} \"$class_{index}\")\"\n",
+            shell_quote(&json_string(&class.name)),
+        ));
+    }
+
+    for (index, contract) in contracts.deploy.iter().flatten().enumerate() {
+        let class_hash = match declare.iter().position(|c| c.name == contract.class) {
+            Some(class_index) => format!("\"$class_{}\"", class_index),
+            None if contract.class.starts_with("0x") => shell_quote(&contract.class),
+            None => {
+                return Err(Error::InvalidSpec(format!(
+                    "bootstrap deploy {} references unknown class {}",
+                    contract.name, contract.class
+                )))
+            }
+        };
+        let inputs = match &contract.calldata {
+            Some(calldata) if !calldata.is_empty() => {
+                let calldata: Vec<_> = calldata.iter().map(|c| shell_quote(c)).collect();
+                format!(" --inputs {}", calldata.join(" "))
+            }
+            _ => String::new(),
+        };
+        script.push_str(&format!(
+            "address_{index}=$(starknet deploy $gateway --class_hash {}{} | sed -n 's/^Contract address: //p')\ntest -n \"$address_{index}\"\n",
+            class_hash, inputs,
+        ));
+        script.push_str(&format!(
+            "addresses=\"$addresses,$(printf '%s:\"%s\"' {} \"$address_{index}\")\"\n",
+            shell_quote(&json_string(&contract.name)),
+        ));
+    }
+
+    script.push_str(
+        r#"printf '{"class_hashes":{%s},"addresses":{%s}}' "${class_hashes#,}" "${addresses#,}" > /dev/termination-log
+"#,
+    );
+    Ok(script)
+}
+
+/// Returns an error for reserved or duplicate step names, and for steps that can't run.
+fn validate_steps(steps: &[BootstrapStep]) -> Result<()> {
+    for (index, step) in steps.iter().enumerate() {
+        // the Dojo migration step is tracked by name in the status, like the other steps.
+        if step.name == DOJO_STEP {
+            return Err(Error::InvalidSpec(format!(
+                "bootstrap step name {} is reserved for the Dojo world migration",
+                step.name
+            )));
+        }
+        if steps[..index].iter().any(|s| s.name == step.name) {
+            return Err(Error::InvalidSpec(format!(
+                "bootstrap step name {} is not unique",
+                step.name
+            )));
+        }
+        match (&step.image, &step.contracts) {
+            (Some(_), None) => {}
+            (None, Some(contracts)) => {
+                if contracts.config_map.is_some() == contracts.image.is_some() {
+                    return Err(Error::InvalidSpec(format!(
+                        "bootstrap step {} contracts need exactly one of config_map or image",
+                        step.name
+                    )));
+                }
+                contracts_script(contracts)?;
+            }
+            _ => {
+                return Err(Error::InvalidSpec(format!(
+                    "bootstrap step {} needs exactly one of image or contracts",
+                    step.name
+                )))
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Returns the name if it's short enough, or its beginning followed by a hash of the whole name.
+fn job_name(name: &str) -> String {
+    if name.len() <= MAX_JOB_NAME_LENGTH {
+        return name.to_string();
+    }
+
+    // the hash is stable across operator versions, so existing jobs keep their name.
+    let hash = short_hash(name);
+    let prefix: String = name
+        .chars()
+        .take(MAX_JOB_NAME_LENGTH - hash.len() - 1)
+        .collect();
+    format!("{}-{}", prefix.trim_end_matches('-'), hash)
+}
+
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+fn json_string(value: &str) -> String {
+    serde_json::to_string(value).expect("string serializes")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bootstrap::{BootstrapDeclare, BootstrapDeploy};
+
+    #[test]
+    fn shell_quote_escapes_single_quotes() {
+        assert_eq!(shell_quote("plain"), "'plain'");
+        assert_eq!(shell_quote("it's"), r"'it'\''s'");
+        assert_eq!(shell_quote("$HOME `id`"), "'$HOME `id`'");
+    }
+
+    #[test]
+    fn contracts_script_declares_then_deploys() {
+        let contracts = BootstrapContracts {
+            declare: Some(vec![BootstrapDeclare {
+                name: "token".to_string(),
+                artifact: "token.json".to_string(),
+            }]),
+            deploy: Some(vec![
+                BootstrapDeploy {
+                    name: "eth".to_string(),
+                    class: "token".to_string(),
+                    calldata: Some(vec!["0x1".to_string(), "it's".to_string()]),
+                },
+                BootstrapDeploy {
+                    name: "account".to_string(),
+                    class: "0x123".to_string(),
+                    calldata: None,
+                },
+            ]),
+            ..BootstrapContracts::default()
+        };
+
+        let script = contracts_script(&contracts).unwrap();
+        let declare = script
+            .find("starknet declare $gateway --contract '/artifacts/token.json'")
+            .unwrap();
+        let deploy = script
+            .find(r#"starknet deploy $gateway --class_hash "$class_0" --inputs '0x1' 'it'\''s'"#)
+            .unwrap();
+        assert!(declare < deploy);
+        assert!(script.contains("starknet deploy $gateway --class_hash '0x123' |"));
+        assert!(script.contains(r#"'"eth"'"#));
+        assert!(script.contains("--chain_id $STARKNET_CHAIN_ID"));
+        assert!(!script.contains("alpha-goerli"));
+        assert!(script.ends_with("> /dev/termination-log\n"));
+    }
+
+    #[test]
+    fn contracts_script_rejects_unknown_classes() {
+        let contracts = BootstrapContracts {
+            deploy: Some(vec![BootstrapDeploy {
+                name: "eth".to_string(),
+                class: "token".to_string(),
+                calldata: None,
+            }]),
+            ..BootstrapContracts::default()
+        };
+
+        assert!(contracts_script(&contracts).is_err());
+    }
+
+    #[test]
+    fn validate_steps_rejects_steps_that_cant_run() {
+        let image = |name: &str| BootstrapStep {
+            name: name.to_string(),
+            image: Some("deployer".to_string()),
+            ..BootstrapStep::default()
+        };
+        assert!(validate_steps(&[image("deploy"), image("seed")]).is_ok());
+        assert!(validate_steps(&[image(DOJO_STEP)]).is_err());
+        assert!(validate_steps(&[image("deploy"), image("deploy")]).is_err());
+
+        let neither = BootstrapStep {
+            name: "deploy".to_string(),
+            ..BootstrapStep::default()
+        };
+        assert!(validate_steps(&[neither]).is_err());
+
+        let both = BootstrapStep {
+            contracts: Some(BootstrapContracts {
+                config_map: Some("artifacts".to_string()),
+                ..BootstrapContracts::default()
+            }),
+            ..image("deploy")
+        };
+        assert!(validate_steps(&[both]).is_err());
+
+        let contracts = |config_map: Option<&str>, image: Option<&str>| BootstrapStep {
+            name: "deploy".to_string(),
+            contracts: Some(BootstrapContracts {
+                config_map: config_map.map(str::to_string),
+                image: image.map(str::to_string),
+                ..BootstrapContracts::default()
+            }),
+            ..BootstrapStep::default()
+        };
+        assert!(validate_steps(&[contracts(Some("artifacts"), None)]).is_ok());
+        assert!(validate_steps(&[contracts(None, Some("artifacts"))]).is_ok());
+        assert!(validate_steps(&[contracts(None, None)]).is_err());
+        assert!(validate_steps(&[contracts(Some("artifacts"), Some("artifacts"))]).is_err());
+    }
+
+    #[test]
+    fn job_name_is_a_valid_label_value() {
+        assert_eq!(job_name("my-devnet-deploy-1a2b3"), "my-devnet-deploy-1a2b3");
+
+        let long = format!("{}-deploy-1a2b3", "a".repeat(60));
+        let name = job_name(&long);
+        assert_eq!(name.len(), MAX_JOB_NAME_LENGTH);
+        assert_eq!(name, job_name(&long));
+        assert_ne!(name, job_name(&format!("{}-deploy-4c5d6", "a".repeat(60))));
+    }
+}
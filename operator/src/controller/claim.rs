@@ -0,0 +1,128 @@
+use std::{sync::Arc, time::Duration};
+
+use kube::{
+    api::{ListParams, Patch, PatchParams, PostParams},
+    runtime::controller::Action,
+    Api, Resource, ResourceExt,
+};
+use serde_json::json;
+use tracing::{debug, info, warn};
+
+use super::Context;
+use crate::{
+    devnet::Devnet,
+    error::Result,
+    pool::{DevnetClaim, DevnetClaimPhase, DevnetClaimStatus, CLAIM_LABEL, POOL_LABEL},
+    Error,
+};
+
+/// How often pending claims look for a ready devnet.
+const PENDING_CLAIM_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Reconcile claim, binding it to a ready devnet of its pool.
+pub async fn reconcile_claim(claim: Arc<DevnetClaim>, ctx: Arc<Context>) -> Result<Action> {
+    let ns = claim.namespace().expect("claim is namespaced");
+    let name = claim.name_any();
+    let devnets: Api<Devnet> = Api::namespaced(ctx.client.clone(), &ns);
+
+    info!(claim = %name, namespace = %ns, "reconcile claim");
+
+    if claim.metadata.deletion_timestamp.is_some() {
+        // the bound devnet is garbage collected with the claim.
+        return Ok(Action::await_change());
+    }
+
+    // the claim may already be bound, even if its status was not updated.
+    let lp = ListParams::default().labels(&format!("{}={}", CLAIM_LABEL, name));
+    let uid = claim.uid();
+    let bound = devnets.list(&lp).await?.into_iter().find(|devnet| {
+        devnet
+            .owner_references()
+            .iter()
+            .any(|owner| Some(&owner.uid) == uid.as_ref())
+    });
+
+    let devnet = match bound {
+        Some(devnet) => Some(devnet),
+        None => bind(&claim, &devnets).await?,
+    };
+
+    let status = match &devnet {
+        Some(devnet) => DevnetClaimStatus {
+            phase: DevnetClaimPhase::Bound,
+            devnet: Some(devnet.name_any()),
+        },
+        None => DevnetClaimStatus::default(),
+    };
+
+    if claim.status.as_ref() != Some(&status) {
+        let claims: Api<DevnetClaim> = Api::namespaced(ctx.client.clone(), &ns);
+        let new_status = json!({
+            "apiVersion": "ryogoku.stark/v1",
+            "kind": "DevnetClaim",
+            "status": status,
+        });
+        let pp = PatchParams::apply("ryogoku").force();
+        claims
+            .patch_status(&name, &pp, &Patch::Apply(new_status))
+            .await?;
+    }
+
+    match devnet {
+        Some(_) => Ok(Action::await_change()),
+        None => Ok(Action::requeue(PENDING_CLAIM_INTERVAL)),
+    }
+}
+
+/// Bind a ready, unclaimed devnet of the pool to the claim.
+///
+/// The devnet is labeled and owned by the claim, using its resource version to make sure
+/// no other claim bound it concurrently.
+async fn bind(claim: &DevnetClaim, devnets: &Api<Devnet>) -> Result<Option<Devnet>> {
+    let name = claim.name_any();
+    let lp = ListParams::default().labels(&format!(
+        "{}={},!{}",
+        POOL_LABEL, claim.spec.pool, CLAIM_LABEL
+    ));
+    let candidates = devnets
+        .list(&lp)
+        .await?
+        .into_iter()
+        .filter(|devnet| devnet.metadata.deletion_timestamp.is_none() && devnet.is_ready());
+
+    let owner_ref = claim.controller_owner_ref(&()).expect("claim has uid");
+
+    for mut devnet in candidates {
+        let devnet_name = devnet.name_any();
+        devnet
+            .labels_mut()
+            .insert(CLAIM_LABEL.to_string(), name.clone());
+        devnet.metadata.owner_references = Some(vec![owner_ref.clone()]);
+
+        match devnets
+            .replace(&devnet_name, &PostParams::default(), &devnet)
+            .await
+        {
+            Ok(devnet) => {
+                info!(
+                    claim = %name,
+                    devnet = %devnet_name,
+                    namespace = devnet.metadata.namespace,
+                    "devnet claimed"
+                );
+                return Ok(Some(devnet));
+            }
+            Err(kube::Error::Api(err)) if err.code == 409 => {
+                debug!(devnet = %devnet_name, "devnet claimed concurrently, trying next");
+            }
+            Err(err) => return Err(err.into()),
+        }
+    }
+
+    Ok(None)
+}
+
+pub fn error_policy(_claim: Arc<DevnetClaim>, error: &Error, _ctx: Arc<Context>) -> Action {
+    warn!(error = ?error, "claim reconcile failed");
+    Action::requeue(Duration::from_secs(10))
+}
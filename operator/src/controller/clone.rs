@@ -0,0 +1,146 @@
+use std::sync::Arc;
+
+use k8s_openapi::{api::core::v1::Pod, apimachinery::pkg::apis::meta::v1::Status};
+use kube::{api::AttachParams, Api, ResourceExt};
+use serde_json::json;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tracing::info;
+
+use super::{Context, DEVNET_CONTAINER};
+use crate::{
+    devnet::{Devnet, DevnetCloneSource},
+    error::Result,
+    rpc::DevnetClient,
+    Error,
+};
+
+/// Path of the state dump copied between the devnet containers.
+static CLONE_DUMP_PATH: &str = "/tmp/ryogoku-clone.dump";
+
+impl Devnet {
+    /// Load the current state of the source devnet.
+    ///
+    /// The source state is dumped in its container, copied through the operator and loaded
+    /// in the devnet container, so the source doesn't need storage and can be in another
+    /// namespace.
+    pub(super) async fn load_clone(
+        &self,
+        ctx: Arc<Context>,
+        source: &DevnetCloneSource,
+    ) -> Result<()> {
+        let ns = self.namespace().expect("devnet is namespaced");
+        let source_ns = source.namespace.clone().unwrap_or_else(|| ns.clone());
+
+        let source_client =
+            DevnetClient::for_operator(ctx.client.clone(), &source_ns, &source.name);
+        let _: serde_json::Value = source_client
+            .post("gateway", "dump", &json!({ "path": CLONE_DUMP_PATH }))
+            .await?;
+
+        let source_pods: Api<Pod> = Api::namespaced(ctx.client.clone(), &source_ns);
+        let ap = AttachParams::default()
+            .container(DEVNET_CONTAINER)
+            .stderr(false);
+        let mut process = source_pods
+            .exec(&source.name, vec!["cat", CLONE_DUMP_PATH], &ap)
+            .await?;
+        let status = process.take_status().expect("status is taken once");
+        let mut dump = Vec::new();
+        process
+            .stdout()
+            .expect("stdout is attached")
+            .read_to_end(&mut dump)
+            .await
+            .map_err(|err| Error::Exec(err.to_string()))?;
+        process
+            .join()
+            .await
+            .map_err(|err| Error::Exec(err.to_string()))?;
+        exec_result("dumping the source state", status.await)?;
+
+        let pods: Api<Pod> = Api::namespaced(ctx.client.clone(), &ns);
+        let ap = AttachParams::default()
+            .container(DEVNET_CONTAINER)
+            .stdin(true)
+            .stdout(false)
+            .stderr(false);
+        let command = format!("cat > {}", CLONE_DUMP_PATH);
+        let mut process = pods
+            .exec(&self.name_any(), vec!["sh", "-c", command.as_str()], &ap)
+            .await?;
+        let status = process.take_status().expect("status is taken once");
+        let mut stdin = process.stdin().expect("stdin is attached");
+        stdin
+            .write_all(&dump)
+            .await
+            .map_err(|err| Error::Exec(err.to_string()))?;
+        stdin
+            .shutdown()
+            .await
+            .map_err(|err| Error::Exec(err.to_string()))?;
+        drop(stdin);
+        process
+            .join()
+            .await
+            .map_err(|err| Error::Exec(err.to_string()))?;
+        exec_result("writing the state", status.await)?;
+
+        let client = DevnetClient::for_operator(ctx.client.clone(), &ns, &self.name_any());
+        let _: serde_json::Value = client
+            .post("gateway", "load", &json!({ "path": CLONE_DUMP_PATH }))
+            .await?;
+
+        info!(
+            devnet = self.name_any(),
+            namespace = %ns,
+            source = %source.name,
+            source_namespace = %source_ns,
+            bytes = dump.len(),
+            "devnet state cloned"
+        );
+        Ok(())
+    }
+}
+
+/// Returns an error unless the exec status reports a success. Commands exiting with a non-zero
+/// code report a failure.
+fn exec_result(action: &str, status: Option<Status>) -> Result<()> {
+    match status {
+        Some(status) if status.status.as_deref() == Some("Success") => Ok(()),
+        Some(status) => Err(Error::Exec(format!(
+            "{} failed: {}",
+            action,
+            status
+                .message
+                .or(status.reason)
+                .unwrap_or_else(|| "unknown reason".to_string())
+        ))),
+        None => Err(Error::Exec(format!("{} failed: no exit status", action))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exec_result_requires_a_success_status() {
+        let status = |status: &str, message: Option<&str>| Status {
+            status: Some(status.to_string()),
+            message: message.map(str::to_string),
+            ..Status::default()
+        };
+        assert!(exec_result("dumping", Some(status("Success", None))).is_ok());
+
+        let err = exec_result(
+            "dumping",
+            Some(status(
+                "Failure",
+                Some("command terminated with non-zero exit code: exit status 1"),
+            )),
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("non-zero exit code"));
+        assert!(exec_result("dumping", None).is_err());
+    }
+}
@@ -0,0 +1,286 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use k8s_openapi::{
+    api::core::v1::{
+        Container, ContainerPort, EmptyDirVolumeSource, Pod, PodSpec, Service, ServicePort,
+        ServiceSpec, Volume, VolumeMount,
+    },
+    apimachinery::pkg::util::intstr::IntOrString,
+};
+use kube::{api::DeleteParams, Api, ResourceExt};
+use tracing::info;
+
+use super::{Context, RPC_PATH, RPC_PORT};
+use crate::{
+    bootstrap::BootstrapStep,
+    devnet::{Devnet, DevnetStatus},
+    dojo::{DevnetDojo, DojoStatus, DojoTorii, DOJO_STEP},
+    error::Result,
+};
+
+static DEFAULT_DOJO_IMAGE: &str = "ghcr.io/dojoengine/dojo:latest";
+/// Mount path of the Dojo project.
+static PROJECT_PATH: &str = "/project";
+/// Annotation recording the world indexed by the Torii pod.
+static WORLD_ANNOTATION: &str = "ryogoku.stark/world_address";
+const TORII_PORT: i32 = 8080;
+
+impl Devnet {
+    /// Returns the bootstrap steps, starting with the Dojo world migration.
+    pub(super) fn bootstrap_steps(&self) -> Vec<BootstrapStep> {
+        let migrate = self.spec.dojo.as_ref().map(|_| BootstrapStep {
+            name: DOJO_STEP.to_string(),
+            ..BootstrapStep::default()
+        });
+        migrate
+            .into_iter()
+            .chain(self.spec.bootstrap.clone().unwrap_or_default())
+            .collect()
+    }
+
+    /// Start the Torii indexer once the world is migrated, returning the world status.
+    ///
+    /// The Torii pod and service are deleted when Torii is removed from the spec.
+    pub(super) async fn setup_dojo(
+        &self,
+        ctx: Arc<Context>,
+        status: &DevnetStatus,
+    ) -> Result<Option<DojoStatus>> {
+        let torii_started = status
+            .dojo
+            .as_ref()
+            .map(|dojo| dojo.torii_graphql.is_some())
+            .unwrap_or(false);
+        let torii = self.spec.dojo.as_ref().and_then(|dojo| dojo.torii.as_ref());
+        if torii_started && torii.is_none() {
+            self.delete_torii(ctx.clone()).await?;
+        }
+
+        let dojo = match &self.spec.dojo {
+            Some(dojo) => dojo,
+            None => return Ok(None),
+        };
+
+        let world_address = status
+            .bootstrap
+            .as_ref()
+            .and_then(|bootstrap| bootstrap.step(DOJO_STEP))
+            .and_then(|step| step.addresses.as_ref())
+            .and_then(|addresses| addresses.get("world"))
+            .cloned();
+
+        let mut dojo_status = DojoStatus {
+            world_address: world_address.clone(),
+            ..DojoStatus::default()
+        };
+
+        if let (Some(torii), Some(world_address)) = (&dojo.torii, &world_address) {
+            self.setup_torii(ctx, dojo, torii, world_address).await?;
+
+            let ns = self.namespace().expect("devnet is namespaced");
+            let host = format!("{}.{}.svc.cluster.local", self.torii_name(), ns);
+            dojo_status.torii_graphql = Some(format!("http://{}:{}/graphql", host, TORII_PORT));
+        }
+
+        Ok(Some(dojo_status))
+    }
+
+    /// Apply the Torii pod and service, replacing the pod if it indexes another world.
+    async fn setup_torii(
+        &self,
+        ctx: Arc<Context>,
+        dojo: &DevnetDojo,
+        torii: &DojoTorii,
+        world_address: &str,
+    ) -> Result<()> {
+        let ns = self.namespace().expect("devnet is namespaced");
+        let pods: Api<Pod> = Api::namespaced(ctx.client.clone(), &ns);
+        let services: Api<Service> = Api::namespaced(ctx.client.clone(), &ns);
+        let name = self.torii_name();
+
+        let existing = pods.get_opt(&name).await?;
+        let indexed_world = existing
+            .as_ref()
+            .and_then(|pod| pod.annotations().get(WORLD_ANNOTATION).cloned());
+
+        match existing {
+            Some(pod) if pod.metadata.deletion_timestamp.is_some() => {
+                // wait for the old pod to go away.
+            }
+            Some(pod) if indexed_world.as_deref() != Some(world_address) => {
+                pods.delete(&pod.name_any(), &DeleteParams::default())
+                    .await?;
+                info!(
+                    pod = pod.name_any(),
+                    namespace = pod.metadata.namespace,
+                    "torii pod deleted, the world was migrated again"
+                );
+            }
+            _ => {
+                let pod_manifest = self.torii_pod_manifest(dojo, torii, world_address);
+                self.apply_owned(ctx.clone(), &pods, &pod_manifest).await?;
+            }
+        }
+
+        self.apply_owned(ctx, &services, &self.torii_service_manifest())
+            .await?;
+        Ok(())
+    }
+
+    /// Delete the Torii pod and service.
+    async fn delete_torii(&self, ctx: Arc<Context>) -> Result<()> {
+        let ns = self.namespace().expect("devnet is namespaced");
+        let pods: Api<Pod> = Api::namespaced(ctx.client.clone(), &ns);
+        let services: Api<Service> = Api::namespaced(ctx.client.clone(), &ns);
+
+        self.delete_owned(&pods, &self.torii_name()).await?;
+        self.delete_owned(&services, &self.torii_name()).await
+    }
+
+    fn torii_name(&self) -> String {
+        format!("{}-torii", self.name_any())
+    }
+
+    /// Torii metadata. Its labels must not match the devnet service selector.
+    fn torii_metadata(&self) -> kube::api::ObjectMeta {
+        let mut metadata = self.object_metadata();
+        metadata.name = Some(self.torii_name());
+        metadata.labels = Some(self.torii_labels());
+        metadata
+    }
+
+    fn torii_labels(&self) -> BTreeMap<String, String> {
+        BTreeMap::from([
+            ("app.kubernetes.io/name".to_string(), self.torii_name()),
+            ("ryogoku.stark/torii".to_string(), self.name_any()),
+        ])
+    }
+
+    fn torii_pod_manifest(&self, dojo: &DevnetDojo, torii: &DojoTorii, world_address: &str) -> Pod {
+        let image = torii
+            .image
+            .clone()
+            .or_else(|| dojo.image.clone())
+            .unwrap_or_else(|| DEFAULT_DOJO_IMAGE.to_string());
+
+        let ns = self.namespace().expect("devnet is namespaced");
+        let rpc = format!(
+            "http://{}.{}.svc.cluster.local:{}{}",
+            self.name_any(),
+            ns,
+            RPC_PORT,
+            RPC_PATH
+        );
+        let mut args = vec![
+            format!("--world={}", world_address),
+            format!("--rpc={}", rpc),
+            format!("--addr=0.0.0.0:{}", TORII_PORT),
+        ];
+        if let Some(extra_args) = &torii.extra_args {
+            args.extend(extra_args.clone());
+        }
+
+        let mut metadata = self.torii_metadata();
+        metadata.annotations = Some(BTreeMap::from([(
+            WORLD_ANNOTATION.to_string(),
+            world_address.to_string(),
+        )]));
+
+        Pod {
+            metadata,
+            spec: Some(PodSpec {
+                containers: vec![Container {
+                    name: "torii".to_string(),
+                    image: Some(image),
+                    command: Some(vec!["torii".to_string()]),
+                    args: Some(args),
+                    ports: Some(vec![ContainerPort {
+                        container_port: TORII_PORT,
+                        name: Some("torii".to_string()),
+                        ..ContainerPort::default()
+                    }]),
+                    ..Container::default()
+                }],
+                ..PodSpec::default()
+            }),
+            status: None,
+        }
+    }
+
+    fn torii_service_manifest(&self) -> Service {
+        Service {
+            metadata: self.torii_metadata(),
+            spec: Some(ServiceSpec {
+                selector: Some(self.torii_labels()),
+                ports: Some(vec![ServicePort {
+                    name: Some("torii".to_string()),
+                    port: TORII_PORT,
+                    target_port: Some(IntOrString::String("torii".to_string())),
+                    ..ServicePort::default()
+                }]),
+                ..ServiceSpec::default()
+            }),
+            status: None,
+        }
+    }
+
+    /// Pod migrating the Dojo world with `sozo`, recording the world address in the
+    /// termination message.
+    pub(super) fn dojo_migrate_pod_spec(&self, dojo: &DevnetDojo) -> PodSpec {
+        let image = dojo
+            .image
+            .clone()
+            .unwrap_or_else(|| DEFAULT_DOJO_IMAGE.to_string());
+
+        let volume = Volume {
+            name: "project".to_string(),
+            empty_dir: Some(EmptyDirVolumeSource::default()),
+            ..Volume::default()
+        };
+        let mount = VolumeMount {
+            name: "project".to_string(),
+            mount_path: PROJECT_PATH.to_string(),
+            ..VolumeMount::default()
+        };
+
+        let script = format!(
+            r#"set -eu
+cd {project}
+sozo migrate --rpc-url "$STARKNET_RPC" --account-address "$STARKNET_ACCOUNT_ADDRESS" --private-key "$STARKNET_PRIVATE_KEY" | tee /tmp/migrate.log
+world=$(grep -o 'World at address 0x[0-9a-fA-F]*' /tmp/migrate.log | tail -n 1 | awk '{{print $4}}')
+test -n "$world"
+printf '{{"addresses":{{"world":"%s"}}}}' "$world" > /dev/termination-log
+"#,
+            project = PROJECT_PATH,
+        );
+
+        PodSpec {
+            // copy the project out of its image before migrating.
+            init_containers: Some(vec![Container {
+                name: "project".to_string(),
+                image: Some(dojo.project_image.clone()),
+                command: Some(vec![
+                    "cp".to_string(),
+                    "-r".to_string(),
+                    format!("{}/.", PROJECT_PATH),
+                    "/out".to_string(),
+                ]),
+                volume_mounts: Some(vec![VolumeMount {
+                    name: "project".to_string(),
+                    mount_path: "/out".to_string(),
+                    ..VolumeMount::default()
+                }]),
+                ..Container::default()
+            }]),
+            containers: vec![Container {
+                name: "bootstrap".to_string(),
+                image: Some(image),
+                command: Some(vec!["/bin/sh".to_string(), "-c".to_string(), script]),
+                volume_mounts: Some(vec![mount]),
+                ..Container::default()
+            }],
+            volumes: Some(vec![volume]),
+            ..PodSpec::default()
+        }
+    }
+}
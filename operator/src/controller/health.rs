@@ -0,0 +1,210 @@
+use std::{sync::Arc, time::Duration};
+
+use k8s_openapi::{api::core::v1::Pod, apimachinery::pkg::apis::meta::v1::Time, chrono::Utc};
+use kube::ResourceExt;
+use tracing::{debug, warn};
+
+use super::{pod_ready_condition, Context, DEFAULT_IMAGE};
+use crate::{
+    devnet::{Devnet, DevnetCondition, DevnetHealth, DevnetStatus},
+    error::Result,
+    rpc::DevnetClient,
+};
+
+/// How often the devnet rpc endpoint is checked.
+pub(super) const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+/// How often the check times are recorded when nothing else changed in the status.
+const HEALTH_RECORD_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+impl Devnet {
+    /// Check the devnet rpc endpoint through its service and record the chain in the status.
+    ///
+    /// The endpoint is checked at most once every `HEALTH_CHECK_INTERVAL`. Status updates
+    /// trigger a new reconcile, so the check times alone are only recorded every
+    /// `HEALTH_RECORD_INTERVAL`, see `only_check_times_changed`.
+    pub(super) async fn check_health(
+        &self,
+        ctx: Arc<Context>,
+        pod: &Pod,
+        status: &mut DevnetStatus,
+    ) {
+        if !pod_ready_condition(pod).is_true() {
+            self.set_pod_not_ready(status);
+            return;
+        }
+        if self.until_health_check(status) > Duration::ZERO {
+            return;
+        }
+
+        let ns = self.namespace().expect("devnet is namespaced");
+        let client = DevnetClient::for_operator(ctx.client.clone(), &ns, &self.name_any());
+        let mut health = status.health.clone().unwrap_or_default();
+        let now = Time(Utc::now());
+        health.last_check_time = Some(now.clone());
+        health.image_tag = image_tag(self.spec.image.as_deref().unwrap_or(DEFAULT_IMAGE));
+
+        match rpc_health(&client).await {
+            Ok((block_number, chain_id)) => {
+                debug!(
+                    devnet = self.name_any(),
+                    namespace = self.metadata.namespace,
+                    block_number,
+                    chain_id = %chain_id,
+                    "rpc reachable"
+                );
+                health.block_number = Some(block_number);
+                health.chain_id = Some(chain_id);
+                // older devnets don't implement it.
+                health.rpc_version = client.spec_version().await.ok();
+                health.last_seen_time = Some(now);
+                status.set_condition(DevnetCondition::new(
+                    "RpcReachable",
+                    true,
+                    "RpcReachable",
+                    None,
+                ));
+            }
+            Err(err) => {
+                warn!(
+                    devnet = self.name_any(),
+                    namespace = self.metadata.namespace,
+                    error = %err,
+                    "rpc unreachable"
+                );
+                status.set_condition(DevnetCondition::new(
+                    "RpcReachable",
+                    false,
+                    "RpcUnreachable",
+                    Some(err.to_string()),
+                ));
+            }
+        }
+
+        #[cfg(feature = "metrics")]
+        crate::metrics::record(self, status.health.as_ref(), &health);
+
+        status.health = Some(health);
+    }
+
+    /// Report the rpc endpoint as unreachable while the devnet pod is not ready.
+    pub(super) fn set_pod_not_ready(&self, status: &mut DevnetStatus) {
+        status.set_condition(DevnetCondition::new(
+            "RpcReachable",
+            false,
+            "PodNotReady",
+            Some("waiting for the devnet pod".to_string()),
+        ));
+
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_unreachable(self);
+    }
+
+    /// Returns the time until the next health check, zero if it's due.
+    pub(super) fn until_health_check(&self, status: &DevnetStatus) -> Duration {
+        let last_check = status
+            .health
+            .as_ref()
+            .and_then(|health| health.last_check_time.as_ref());
+        match last_check {
+            Some(last_check) => {
+                let elapsed = (Utc::now() - last_check.0).to_std().unwrap_or_default();
+                HEALTH_CHECK_INTERVAL.saturating_sub(elapsed)
+            }
+            None => Duration::ZERO,
+        }
+    }
+}
+
+/// Returns true if the new status only differs from the current one by its health check
+/// times, and these were recorded less than `HEALTH_RECORD_INTERVAL` ago.
+pub(super) fn only_check_times_changed(current: &DevnetStatus, status: &DevnetStatus) -> bool {
+    let (current_health, health) = match (&current.health, &status.health) {
+        (Some(current_health), Some(health)) => (current_health, health),
+        _ => return false,
+    };
+    let recorded = current_health
+        .last_check_time
+        .as_ref()
+        .and_then(|t| (Utc::now() - t.0).to_std().ok())
+        .map(|elapsed| elapsed < HEALTH_RECORD_INTERVAL)
+        .unwrap_or(false);
+    if !recorded {
+        return false;
+    }
+
+    let status = DevnetStatus {
+        health: Some(DevnetHealth {
+            last_check_time: current_health.last_check_time.clone(),
+            last_seen_time: current_health.last_seen_time.clone(),
+            ..health.clone()
+        }),
+        ..status.clone()
+    };
+    &status == current
+}
+
+async fn rpc_health(client: &DevnetClient) -> Result<(u64, String)> {
+    let block_number = client.block_number().await?;
+    let chain_id = client.chain_id().await?;
+    Ok((block_number, chain_id))
+}
+
+/// Returns the tag of the image, without its digest, or `None` for untagged images.
+fn image_tag(image: &str) -> Option<String> {
+    let image = image.split('@').next().unwrap_or_default();
+    let (name, tag) = image.rsplit_once(':')?;
+    // the colon of a registry port is followed by the image path.
+    (!tag.contains('/') && !name.is_empty()).then(|| tag.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn image_tag_ignores_registry_ports_and_digests() {
+        assert_eq!(
+            image_tag("shardlabs/starknet-devnet:0.5.3").as_deref(),
+            Some("0.5.3")
+        );
+        assert_eq!(
+            image_tag("registry:5000/starknet-devnet:latest@sha256:abc").as_deref(),
+            Some("latest")
+        );
+        assert_eq!(image_tag("registry:5000/starknet-devnet"), None);
+        assert_eq!(image_tag("starknet-devnet@sha256:abc"), None);
+    }
+
+    #[test]
+    fn check_times_are_recorded_every_few_minutes() {
+        let status = |checked: Time, block_number: u64| DevnetStatus {
+            health: Some(DevnetHealth {
+                block_number: Some(block_number),
+                last_check_time: Some(checked.clone()),
+                last_seen_time: Some(checked),
+                ..DevnetHealth::default()
+            }),
+            ..DevnetStatus::default()
+        };
+        let now = Utc::now();
+        let recent = Time(now - k8s_openapi::chrono::Duration::seconds(30));
+        let old = Time(now - k8s_openapi::chrono::Duration::minutes(10));
+
+        assert!(only_check_times_changed(
+            &status(recent.clone(), 1),
+            &status(Time(now), 1)
+        ));
+        assert!(!only_check_times_changed(
+            &status(recent, 1),
+            &status(Time(now), 2)
+        ));
+        assert!(!only_check_times_changed(
+            &status(old, 1),
+            &status(Time(now), 1)
+        ));
+        assert!(!only_check_times_changed(
+            &DevnetStatus::default(),
+            &status(Time(now), 1)
+        ));
+    }
+}
@@ -0,0 +1,207 @@
+use std::sync::Arc;
+
+use k8s_openapi::{
+    api::core::v1::{Container, ContainerPort, Pod, ServicePort},
+    apimachinery::pkg::util::intstr::IntOrString,
+};
+use kube::ResourceExt;
+use serde::Deserialize;
+use serde_json::json;
+use tracing::{info, warn};
+
+use super::{pod_ready_condition, Context};
+use crate::{
+    devnet::{Devnet, DevnetCondition, DevnetL1, DevnetStatus},
+    rpc::DevnetClient,
+};
+
+static DEFAULT_L1_IMAGE: &str = "ghcr.io/foundry-rs/foundry:latest";
+const L1_PORT: i32 = 8545;
+
+/// Response of `postman/load_l1_messaging_contract`.
+#[derive(Deserialize)]
+struct LoadMessagingContract {
+    address: String,
+}
+
+impl Devnet {
+    /// Load the L1 messaging contract into the L1 node once per pod.
+    ///
+    /// Errors are logged and ignored, the L1 node may still be starting. The devnet is not
+    /// ready until the contract is loaded.
+    pub(super) async fn setup_l1_messaging(
+        &self,
+        ctx: Arc<Context>,
+        pod: &Pod,
+        status: &mut DevnetStatus,
+    ) {
+        if self.spec.l1.is_none() {
+            return;
+        }
+
+        let ns = self.namespace().expect("devnet is namespaced");
+        let mut l1 = status.l1.clone().unwrap_or_default();
+        l1.rpc = format!(
+            "http://{}.{}.svc.cluster.local:{}",
+            self.name_any(),
+            ns,
+            L1_PORT
+        );
+        if l1.pod_uid != pod.uid() {
+            l1.messaging_contract_address = None;
+            l1.pod_uid = pod.uid();
+        }
+
+        if l1.messaging_contract_address.is_none() && pod_ready_condition(pod).is_true() {
+            let client = DevnetClient::for_operator(ctx.client.clone(), &ns, &self.name_any());
+            // the L1 node runs in the devnet pod.
+            let body = json!({ "networkUrl": format!("http://127.0.0.1:{}", L1_PORT) });
+            let response: crate::Result<LoadMessagingContract> = client
+                .post("gateway", "postman/load_l1_messaging_contract", &body)
+                .await;
+            match response {
+                Ok(response) => {
+                    info!(
+                        devnet = self.name_any(),
+                        namespace = self.metadata.namespace,
+                        address = %response.address,
+                        "l1 messaging contract loaded"
+                    );
+                    l1.messaging_contract_address = Some(response.address);
+                }
+                Err(err) => {
+                    warn!(
+                        devnet = self.name_any(),
+                        namespace = self.metadata.namespace,
+                        error = %err,
+                        "failed to load l1 messaging contract"
+                    );
+                }
+            }
+        }
+
+        status.l1 = Some(l1);
+    }
+
+    /// Returns true if the devnet waits for its L1 messaging contract.
+    pub(super) fn l1_pending(&self, pod: &Pod, status: &DevnetStatus) -> bool {
+        self.spec.l1.is_some()
+            && status
+                .l1
+                .as_ref()
+                .filter(|l1| l1.pod_uid == pod.uid())
+                .and_then(|l1| l1.messaging_contract_address.as_ref())
+                .is_none()
+    }
+
+    /// Returns the `Ready` condition while the L1 messaging contract is not loaded.
+    pub(super) fn l1_condition(&self, pod: &Pod, status: &DevnetStatus) -> Option<DevnetCondition> {
+        self.l1_pending(pod, status).then(|| {
+            DevnetCondition::new(
+                "Ready",
+                false,
+                "LoadingL1Messaging",
+                Some("waiting for the l1 messaging contract".to_string()),
+            )
+        })
+    }
+}
+
+/// Container running the L1 node.
+pub(super) fn l1_container(l1: &DevnetL1) -> Container {
+    let image = l1
+        .image
+        .clone()
+        .unwrap_or_else(|| DEFAULT_L1_IMAGE.to_string());
+    let mut args = vec!["--host=0.0.0.0".to_string(), format!("--port={}", L1_PORT)];
+    if let Some(extra_args) = &l1.extra_args {
+        args.extend(extra_args.clone());
+    }
+
+    Container {
+        name: "l1".to_string(),
+        image: Some(image),
+        command: Some(vec!["anvil".to_string()]),
+        args: Some(args),
+        ports: Some(vec![ContainerPort {
+            container_port: L1_PORT,
+            name: Some("l1".to_string()),
+            ..ContainerPort::default()
+        }]),
+        ..Container::default()
+    }
+}
+
+/// Service port of the L1 node.
+pub(super) fn l1_service_port() -> ServicePort {
+    ServicePort {
+        name: Some("l1".to_string()),
+        port: L1_PORT,
+        target_port: Some(IntOrString::String("l1".to_string())),
+        ..ServicePort::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::devnet::{DevnetL1Status, DevnetSpec};
+
+    #[test]
+    fn l1_container_runs_anvil_on_the_l1_port() {
+        let container = l1_container(&DevnetL1::default());
+        assert_eq!(container.image.as_deref(), Some(DEFAULT_L1_IMAGE));
+        assert_eq!(container.command, Some(vec!["anvil".to_string()]));
+        assert_eq!(
+            container.args,
+            Some(vec![
+                "--host=0.0.0.0".to_string(),
+                "--port=8545".to_string()
+            ])
+        );
+
+        let container = l1_container(&DevnetL1 {
+            image: Some("foundry:nightly".to_string()),
+            extra_args: Some(vec!["--block-time=2".to_string()]),
+        });
+        assert_eq!(container.image.as_deref(), Some("foundry:nightly"));
+        assert_eq!(
+            container.args.unwrap().last().map(String::as_str),
+            Some("--block-time=2")
+        );
+    }
+
+    #[test]
+    fn l1_is_pending_until_loaded_in_the_current_pod() {
+        let devnet = Devnet::new(
+            "my-devnet",
+            DevnetSpec {
+                l1: Some(DevnetL1::default()),
+                ..DevnetSpec::default()
+            },
+        );
+        let mut pod = Pod::default();
+        pod.metadata.uid = Some("pod-2".to_string());
+        let status = |pod_uid: &str| DevnetStatus {
+            l1: Some(DevnetL1Status {
+                messaging_contract_address: Some("0x1".to_string()),
+                pod_uid: Some(pod_uid.to_string()),
+                ..DevnetL1Status::default()
+            }),
+            ..DevnetStatus::default()
+        };
+
+        assert!(devnet.l1_pending(&pod, &DevnetStatus::default()));
+        assert!(devnet.l1_pending(&pod, &status("pod-1")));
+        assert!(!devnet.l1_pending(&pod, &status("pod-2")));
+        assert_eq!(
+            devnet
+                .l1_condition(&pod, &status("pod-1"))
+                .and_then(|c| c.reason),
+            Some("LoadingL1Messaging".to_string())
+        );
+
+        let without_l1 = Devnet::new("my-devnet", DevnetSpec::default());
+        assert!(!without_l1.l1_pending(&pod, &DevnetStatus::default()));
+    }
+}
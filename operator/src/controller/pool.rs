@@ -0,0 +1,117 @@
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
+
+use kube::{
+    api::{DeleteParams, ListParams, ObjectMeta, Patch, PatchParams, PostParams},
+    runtime::controller::Action,
+    Api, Resource, ResourceExt,
+};
+use serde_json::json;
+use tracing::{info, warn};
+
+use super::Context;
+use crate::{
+    devnet::Devnet,
+    error::Result,
+    pool::{DevnetPool, DevnetPoolStatus, CLAIM_LABEL, POOL_LABEL},
+    Error,
+};
+
+/// Reconcile pool, creating or deleting unclaimed devnets to match the pool replicas.
+pub async fn reconcile_pool(pool: Arc<DevnetPool>, ctx: Arc<Context>) -> Result<Action> {
+    let ns = pool.namespace().expect("pool is namespaced");
+    let name = pool.name_any();
+    let devnets: Api<Devnet> = Api::namespaced(ctx.client.clone(), &ns);
+
+    info!(pool = %name, namespace = %ns, "reconcile pool");
+
+    if pool.metadata.deletion_timestamp.is_some() {
+        // unclaimed devnets are garbage collected with the pool.
+        return Ok(Action::await_change());
+    }
+
+    let lp = ListParams::default().labels(&format!("{}={}", POOL_LABEL, name));
+    let members: Vec<_> = devnets
+        .list(&lp)
+        .await?
+        .into_iter()
+        .filter(|devnet| devnet.metadata.deletion_timestamp.is_none())
+        .collect();
+    let (claimed, mut unclaimed): (Vec<_>, Vec<_>) = members
+        .into_iter()
+        .partition(|devnet| devnet.labels().contains_key(CLAIM_LABEL));
+
+    let replicas = pool.spec.replicas;
+
+    if unclaimed.len() < replicas {
+        for _ in unclaimed.len()..replicas {
+            let devnet = devnets
+                .create(&PostParams::default(), &pool_devnet(&pool))
+                .await?;
+            info!(
+                pool = %name,
+                devnet = devnet.name_any(),
+                namespace = %ns,
+                "pool devnet created"
+            );
+            unclaimed.push(devnet);
+        }
+    } else if unclaimed.len() > replicas {
+        // scale down, deleting devnets that are not ready first.
+        unclaimed.sort_by_key(|devnet| devnet.is_ready());
+        for devnet in unclaimed.drain(..unclaimed.len() - replicas) {
+            devnets
+                .delete(&devnet.name_any(), &DeleteParams::default())
+                .await?;
+            info!(
+                pool = %name,
+                devnet = devnet.name_any(),
+                namespace = %ns,
+                "pool devnet deleted"
+            );
+        }
+    }
+
+    let status = DevnetPoolStatus {
+        replicas: unclaimed.len(),
+        ready_replicas: unclaimed.iter().filter(|d| d.is_ready()).count(),
+        claimed: claimed.len(),
+    };
+
+    if pool.status.as_ref() != Some(&status) {
+        let pools: Api<DevnetPool> = Api::namespaced(ctx.client.clone(), &ns);
+        let new_status = json!({
+            "apiVersion": "ryogoku.stark/v1",
+            "kind": "DevnetPool",
+            "status": status,
+        });
+        let pp = PatchParams::apply("ryogoku").force();
+        pools
+            .patch_status(&name, &pp, &Patch::Apply(new_status))
+            .await?;
+    }
+
+    Ok(Action::await_change())
+}
+
+/// Returns a new devnet for the pool, owned by the pool.
+fn pool_devnet(pool: &DevnetPool) -> Devnet {
+    let name = pool.name_any();
+    let owner_ref = pool.controller_owner_ref(&()).expect("pool has uid");
+
+    Devnet {
+        metadata: ObjectMeta {
+            generate_name: Some(format!("{}-", name)),
+            namespace: pool.namespace(),
+            labels: Some(BTreeMap::from([(POOL_LABEL.to_string(), name)])),
+            owner_references: Some(vec![owner_ref]),
+            ..ObjectMeta::default()
+        },
+        spec: pool.spec.template.clone(),
+        status: None,
+    }
+}
+
+pub fn error_policy(_pool: Arc<DevnetPool>, error: &Error, _ctx: Arc<Context>) -> Action {
+    warn!(error = ?error, "pool reconcile failed");
+    Action::requeue(Duration::from_secs(10))
+}
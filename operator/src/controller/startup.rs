@@ -0,0 +1,236 @@
+use std::sync::Arc;
+
+use k8s_openapi::api::core::v1::Pod;
+use kube::ResourceExt;
+use serde_json::json;
+use tracing::{info, warn};
+
+use super::{pod_ready_condition, Context};
+use crate::{
+    devnet::{Devnet, DevnetCondition, DevnetStatus, FundedAddress},
+    error::Result,
+    rpc::DevnetClient,
+    Error,
+};
+
+impl Devnet {
+    /// Apply the startup settings, like the cloned state, the devnet time and funded addresses,
+    /// once per pod.
+    ///
+    /// Devnets restarting from a state dump keep their state and skip the settings. Errors
+    /// are logged and the settings are applied again on the next reconcile.
+    pub(super) async fn setup_startup(
+        &self,
+        ctx: Arc<Context>,
+        pod: &Pod,
+        status: &mut DevnetStatus,
+    ) {
+        if !self.startup_pending(pod, status) || !pod_ready_condition(pod).is_true() {
+            return;
+        }
+
+        if status.dump_path.is_none() {
+            if let Err(err) = self.apply_startup(ctx, status).await {
+                warn!(
+                    devnet = self.name_any(),
+                    namespace = self.metadata.namespace,
+                    error = %err,
+                    "failed to apply startup settings"
+                );
+                return;
+            }
+        }
+
+        status.startup_pod_uid = pod.uid();
+    }
+
+    async fn apply_startup(&self, ctx: Arc<Context>, status: &mut DevnetStatus) -> Result<()> {
+        let ns = self.namespace().expect("devnet is namespaced");
+        let client = DevnetClient::for_operator(ctx.client.clone(), &ns, &self.name_any());
+
+        // the clone state comes first, the other settings apply on top of it. It's only loaded
+        // once, the source may have changed or be gone since.
+        if let Some(source) = &self.spec.clone_from {
+            if status.cloned_from.is_none() {
+                let source_ns = source.namespace.clone().unwrap_or_else(|| ns.clone());
+                if let Err(err) = self.load_clone(ctx.clone(), source).await {
+                    status.set_condition(DevnetCondition::new(
+                        "Cloned",
+                        false,
+                        "CloneFailed",
+                        Some(format!(
+                            "failed to load the state of {}/{}: {}",
+                            source_ns, source.name, err
+                        )),
+                    ));
+                    return Err(err);
+                }
+                status.cloned_from = Some(format!("{}/{}", source_ns, source.name));
+                status.set_condition(DevnetCondition::new("Cloned", true, "Cloned", None));
+            }
+        }
+
+        let time = self
+            .spec
+            .block_production
+            .as_ref()
+            .and_then(|block_production| block_production.time);
+        if let Some(time) = time {
+            let _: serde_json::Value = client
+                .post("gateway", "set_time", &json!({ "time": time }))
+                .await?;
+            info!(
+                devnet = self.name_any(),
+                namespace = self.metadata.namespace,
+                time,
+                "devnet time set"
+            );
+        }
+
+        for (funded, amount) in self.funded_amounts()? {
+            let response = client.mint(&funded.address, amount, funded.unit).await?;
+            info!(
+                devnet = self.name_any(),
+                namespace = self.metadata.namespace,
+                address = %funded.address,
+                balance = %response.new_balance,
+                "address funded"
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Returns the funded addresses with their parsed amounts.
+    ///
+    /// All amounts are checked before minting, so an invalid one doesn't leave the
+    /// addresses partially funded.
+    fn funded_amounts(&self) -> Result<Vec<(&FundedAddress, u128)>> {
+        self.spec
+            .funded_addresses
+            .iter()
+            .flatten()
+            .map(|funded| {
+                let amount = funded.amount.parse().map_err(|_| {
+                    Error::InvalidSpec(format!(
+                        "invalid amount {} for address {}",
+                        funded.amount, funded.address
+                    ))
+                })?;
+                Ok((funded, amount))
+            })
+            .collect()
+    }
+
+    /// Returns true if the startup settings were not applied to the pod yet.
+    pub(super) fn startup_pending(&self, pod: &Pod, status: &DevnetStatus) -> bool {
+        status.startup_pod_uid != pod.uid()
+    }
+
+    /// Returns the `Ready` condition while the startup settings are not applied.
+    pub(super) fn startup_condition(
+        &self,
+        pod: &Pod,
+        status: &DevnetStatus,
+    ) -> Option<DevnetCondition> {
+        self.startup_pending(pod, status).then(|| {
+            DevnetCondition::new(
+                "Ready",
+                false,
+                "Starting",
+                Some("applying startup settings".to_string()),
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::devnet::{DevnetSpec, MintUnit};
+
+    fn devnet(funded_addresses: Vec<FundedAddress>) -> Devnet {
+        Devnet::new(
+            "my-devnet",
+            DevnetSpec {
+                funded_addresses: Some(funded_addresses),
+                ..DevnetSpec::default()
+            },
+        )
+    }
+
+    #[test]
+    fn funded_amounts_are_parsed_as_u128() {
+        let devnet = devnet(vec![
+            FundedAddress {
+                address: "0x1".to_string(),
+                amount: "1000000000000000000000".to_string(),
+                unit: None,
+            },
+            FundedAddress {
+                address: "0x2".to_string(),
+                amount: "5".to_string(),
+                unit: Some(MintUnit::Fri),
+            },
+        ]);
+        let amounts = devnet.funded_amounts().unwrap();
+        let amounts: Vec<_> = amounts
+            .iter()
+            .map(|(funded, amount)| (funded.address.as_str(), *amount, funded.unit))
+            .collect();
+        assert_eq!(
+            amounts,
+            vec![
+                ("0x1", 1_000_000_000_000_000_000_000, None),
+                ("0x2", 5, Some(MintUnit::Fri)),
+            ]
+        );
+
+        assert!(Devnet::new("my-devnet", DevnetSpec::default())
+            .funded_amounts()
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn invalid_funded_amount_is_rejected() {
+        for amount in ["", "-1", "1e18", "0x10"] {
+            let devnet = devnet(vec![FundedAddress {
+                address: "0x1".to_string(),
+                amount: amount.to_string(),
+                unit: None,
+            }]);
+            match devnet.funded_amounts() {
+                Err(Error::InvalidSpec(message)) => {
+                    assert_eq!(
+                        message,
+                        format!("invalid amount {} for address 0x1", amount)
+                    )
+                }
+                _ => panic!("amount {:?} should be rejected", amount),
+            }
+        }
+    }
+
+    #[test]
+    fn startup_is_pending_until_applied_to_the_current_pod() {
+        let devnet = devnet(Vec::new());
+        let mut pod = Pod::default();
+        pod.metadata.uid = Some("pod-2".to_string());
+
+        let status = DevnetStatus {
+            startup_pod_uid: Some("pod-1".to_string()),
+            ..DevnetStatus::default()
+        };
+        assert!(devnet.startup_pending(&pod, &status));
+        let condition = devnet.startup_condition(&pod, &status).unwrap();
+        assert_eq!(condition.reason.as_deref(), Some("Starting"));
+
+        let status = DevnetStatus {
+            startup_pod_uid: Some("pod-2".to_string()),
+            ..DevnetStatus::default()
+        };
+        assert!(!devnet.startup_pending(&pod, &status));
+        assert!(devnet.startup_condition(&pod, &status).is_none());
+    }
+}
@@ -1,14 +1,35 @@
-use std::fmt::Display;
+use std::{collections::BTreeMap, fmt::Display};
 
-use kube::{core::object::HasStatus, CustomResource};
+use k8s_openapi::{
+    apimachinery::pkg::apis::meta::v1::Time,
+    chrono::{DateTime, Duration, Utc},
+};
+use kube::{core::object::HasStatus, CustomResource, ResourceExt};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::{
+    bootstrap::{BootstrapStatus, BootstrapStep},
+    dojo::{DevnetDojo, DojoStatus},
+    template::DevnetTemplateRef,
+};
+
+/// Annotation opting a devnet out of expiry.
+pub static KEEP_ANNOTATION: &str = "ryogoku.stark/keep";
+/// Annotation restarting the devnet from genesis when its value changes, usually set to
+/// the current time.
+pub static RESTART_ANNOTATION: &str = "ryogoku.stark/restartedAt";
+
+/// Seconds between blocks in `Interval` block production mode.
+const DEFAULT_BLOCK_INTERVAL_SECONDS: u64 = 60;
+
 /// StarkNet development network.
 #[derive(CustomResource, Deserialize, Serialize, Clone, Debug, JsonSchema, Default)]
 #[kube(kind = "Devnet", group = "ryogoku.stark", version = "v1", namespaced)]
 #[kube(status = "DevnetStatus", shortname = "devnet")]
 pub struct DevnetSpec {
+    /// Template providing the fields not set on the devnet.
+    pub template_ref: Option<DevnetTemplateRef>,
     /// The devnet docker image and version. Defaults to `shardlabs/starknet-devnet:latest`.
     pub image: Option<String>,
     /// Applies all `lite-mode-*` optimizations by disabling some features.
@@ -27,33 +48,323 @@ pub struct DevnetSpec {
     pub start_time: Option<u64>,
     /// Specify the gas price in wei.
     pub gas_price: Option<String>,
+    /// How blocks are produced after genesis.
+    pub block_production: Option<BlockProduction>,
     /// Extra arguments for the container.
     pub extra_args: Option<Vec<String>>,
     /// Specify how the service is exposed.
     pub service_type: Option<String>,
+    /// Expose the devnet through an Ingress or a Gateway API HTTPRoute.
+    pub exposure: Option<DevnetExposure>,
+    /// Delete the devnet this many seconds after it was created.
+    pub ttl_seconds_after_creation: Option<u64>,
+    /// Delete the devnet when it served no request and produced no block for this many
+    /// seconds. Requests are read from the devnet access log.
+    pub ttl_seconds_after_idle: Option<u64>,
+    /// Persistent storage for the devnet state. Required to pause the devnet.
+    pub storage: Option<DevnetStorage>,
+    /// Dump the devnet state and stop its pod. Setting it back to false restores the state.
+    pub paused: Option<bool>,
+    /// Steps run in order once the devnet is up. The devnet is `Ready` once they all succeed.
+    pub bootstrap: Option<Vec<BootstrapStep>>,
+    /// Dojo world migrated onto the devnet, with an optional Torii indexer.
+    pub dojo: Option<DevnetDojo>,
+    /// Local L1 node paired with the devnet for L1-L2 messaging.
+    pub l1: Option<DevnetL1>,
+    /// Devnet whose state is copied into this devnet when it first starts.
+    /// Pods recreated later, like on restarts, start from genesis or from the state dump.
+    pub clone_from: Option<DevnetCloneSource>,
+    /// Addresses funded once the devnet starts.
+    /// Not applied when the devnet restarts from a state dump.
+    pub funded_addresses: Option<Vec<FundedAddress>>,
+}
+
+/// Devnet to copy the state from.
+#[derive(Deserialize, Serialize, Clone, Debug, JsonSchema, Default)]
+pub struct DevnetCloneSource {
+    /// Source devnet name.
+    pub name: String,
+    /// Source devnet namespace. Defaults to the devnet namespace.
+    pub namespace: Option<String>,
+}
+
+/// Address funded by the operator.
+#[derive(Deserialize, Serialize, Clone, Debug, JsonSchema, Default)]
+pub struct FundedAddress {
+    /// Address to fund.
+    pub address: String,
+    /// Amount to mint, in the smallest unit.
+    pub amount: String,
+    /// Token to mint. Defaults to `Wei`.
+    pub unit: Option<MintUnit>,
+}
+
+/// Token minted by the devnet.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, JsonSchema, PartialEq, Eq)]
+pub enum MintUnit {
+    /// Ether, in wei.
+    Wei,
+    /// Stark, in fri.
+    Fri,
+}
+
+/// Block production settings.
+#[derive(Deserialize, Serialize, Clone, Debug, JsonSchema, Default)]
+pub struct BlockProduction {
+    /// When blocks are created. Defaults to `Transaction`.
+    pub mode: Option<BlockProductionMode>,
+    /// Seconds between blocks in `Interval` mode. Defaults to 60.
+    pub interval_seconds: Option<u64>,
+    /// Set the devnet time to this Unix time in seconds once it starts.
+    /// Not applied when the devnet restarts from a state dump.
+    pub time: Option<u64>,
+}
+
+/// When blocks are created.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, JsonSchema, PartialEq, Eq, Default)]
+pub enum BlockProductionMode {
+    /// A block is created for every transaction.
+    #[default]
+    Transaction,
+    /// Transactions are pending until a block is created with `create_block`.
+    Demand,
+    /// A block is created every `interval_seconds`. Requires a devnet image supporting
+    /// `--block-generation-on`.
+    Interval,
+}
+
+/// Local L1 node, running next to the devnet in its pod.
+#[derive(Deserialize, Serialize, Clone, Debug, JsonSchema, Default)]
+pub struct DevnetL1 {
+    /// Image providing `anvil`. Defaults to `ghcr.io/foundry-rs/foundry:latest`.
+    pub image: Option<String>,
+    /// Extra arguments for anvil.
+    pub extra_args: Option<Vec<String>>,
+}
+
+/// Persistent storage for the devnet state.
+#[derive(Deserialize, Serialize, Clone, Debug, JsonSchema, Default)]
+pub struct DevnetStorage {
+    /// Size of the volume. Defaults to `1Gi`.
+    pub size: Option<String>,
+    /// Storage class of the volume. Defaults to the cluster default storage class.
+    pub storage_class_name: Option<String>,
+}
+
+/// Devnet exposure outside the cluster.
+#[derive(Deserialize, Serialize, Clone, Debug, JsonSchema, Default)]
+pub struct DevnetExposure {
+    /// The kind of resource used to expose the devnet. Defaults to `Ingress`.
+    pub mode: Option<ExposureMode>,
+    /// Host template. `{name}` and `{namespace}` are replaced with the devnet name and namespace,
+    /// for example `{name}.{namespace}.devnets.example.com`.
+    pub host: String,
+    /// The service port to expose, either `rpc` or `gateway`. Defaults to `rpc`.
+    pub port: Option<String>,
+    /// Ingress class name. Only used in `Ingress` mode.
+    pub ingress_class_name: Option<String>,
+    /// Name of the parent Gateway. Required in `HttpRoute` mode.
+    pub gateway_name: Option<String>,
+    /// Namespace of the parent Gateway. Defaults to the devnet namespace.
+    pub gateway_namespace: Option<String>,
+    /// Extra annotations added to the Ingress or HTTPRoute.
+    pub annotations: Option<BTreeMap<String, String>>,
+    /// Serve the devnet over TLS.
+    pub tls: Option<ExposureTls>,
+}
+
+/// Resource used to expose the devnet.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, JsonSchema, PartialEq, Eq)]
+pub enum ExposureMode {
+    /// Create a `networking.k8s.io/v1` Ingress.
+    Ingress,
+    /// Create a `gateway.networking.k8s.io/v1beta1` HTTPRoute.
+    HttpRoute,
+}
+
+/// TLS configuration of the exposed devnet.
+#[derive(Deserialize, Serialize, Clone, Debug, JsonSchema, Default)]
+pub struct ExposureTls {
+    /// Secret holding the TLS certificate. Defaults to `<devnet name>-tls`.
+    /// In `HttpRoute` mode TLS is terminated by the Gateway and this field is ignored.
+    pub secret_name: Option<String>,
 }
 
 /// State of the devnet.
-#[derive(Deserialize, Serialize, Clone, Copy, Debug, JsonSchema, PartialEq)]
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, JsonSchema, PartialEq, Default)]
 pub enum DevnetState {
     /// Devnet created.
+    #[default]
     Created,
     /// Devnet is running.
     Running,
     /// Devnet started but errored.
     Errored,
+    /// Devnet state was dumped and its pod stopped.
+    Paused,
 }
 
 /// Devnet status.
-#[derive(Deserialize, Serialize, Clone, Default, Debug, JsonSchema)]
+#[derive(Deserialize, Serialize, Clone, Default, Debug, JsonSchema, PartialEq)]
 pub struct DevnetStatus {
     /// Devnet state.
     pub state: DevnetState,
+    /// Public URL of the devnet, if exposed.
+    pub public_url: Option<String>,
+    /// Devnet connection endpoints.
+    pub endpoints: Option<DevnetEndpoints>,
+    /// Latest observations of the devnet state.
+    pub conditions: Option<Vec<DevnetCondition>>,
+    /// Latest block number seen by the operator.
+    pub last_block_number: Option<u64>,
+    /// Last time the operator saw a new block or a request, the start of the idle time-to-live.
+    pub last_activity_time: Option<Time>,
+    /// Time after which the devnet is deleted.
+    pub expires_at: Option<Time>,
+    /// Path of the state dump loaded when the devnet starts.
+    pub dump_path: Option<String>,
+    /// Bootstrap steps progress and results.
+    pub bootstrap: Option<BootstrapStatus>,
+    /// Dojo world address and Torii endpoints.
+    pub dojo: Option<DojoStatus>,
+    /// L1 node and messaging contract.
+    pub l1: Option<DevnetL1Status>,
+    /// Uid of the pod the startup settings, like the devnet time, were applied to.
+    pub startup_pod_uid: Option<String>,
+    /// Devnet whose state was loaded, as `namespace/name`.
+    pub cloned_from: Option<String>,
+    /// Number of times the devnet was restarted.
+    pub restart_count: Option<u32>,
+    /// Last time the devnet was restarted.
+    pub last_restart_time: Option<Time>,
+    /// Value of the `ryogoku.stark/restartedAt` annotation when the devnet was last restarted.
+    pub restarted_at: Option<String>,
+    /// Devnet health, checked periodically through its rpc endpoint.
+    pub health: Option<DevnetHealth>,
+}
+
+/// Devnet health, as seen by the operator through the devnet service.
+#[derive(Deserialize, Serialize, Clone, Default, Debug, JsonSchema, PartialEq)]
+pub struct DevnetHealth {
+    /// Latest block number.
+    pub block_number: Option<u64>,
+    /// Chain id, as returned by `starknet_chainId`.
+    pub chain_id: Option<String>,
+    /// Version of the JSON-RPC specification served by the devnet, if it reports it.
+    pub rpc_version: Option<String>,
+    /// Tag of the devnet image, if it has one.
+    pub image_tag: Option<String>,
+    /// Last time the rpc endpoint was checked. Only recorded every few minutes while the
+    /// health doesn't change.
+    pub last_check_time: Option<Time>,
+    /// Last time the rpc endpoint answered. Only recorded every few minutes while the health
+    /// doesn't change.
+    pub last_seen_time: Option<Time>,
+}
+
+/// L1 node status.
+#[derive(Deserialize, Serialize, Clone, Default, Debug, JsonSchema, PartialEq)]
+pub struct DevnetL1Status {
+    /// In-cluster L1 rpc URL.
+    pub rpc: String,
+    /// Address of the messaging contract on L1, once loaded.
+    pub messaging_contract_address: Option<String>,
+    /// Uid of the pod the messaging contract was loaded in.
+    pub pod_uid: Option<String>,
+}
+
+/// Devnet condition.
+#[derive(Deserialize, Serialize, Clone, Debug, JsonSchema, PartialEq)]
+pub struct DevnetCondition {
+    /// Condition type, for example `Ready`.
+    #[serde(rename = "type")]
+    pub type_: String,
+    /// Condition status, one of `True`, `False` or `Unknown`.
+    pub status: String,
+    /// Machine readable reason for the last transition.
+    pub reason: Option<String>,
+    /// Human readable details about the last transition.
+    pub message: Option<String>,
+    /// Last time the condition status changed.
+    pub last_transition_time: Option<Time>,
+}
+
+/// Devnet connection endpoints.
+#[derive(Deserialize, Serialize, Clone, Default, Debug, JsonSchema, PartialEq)]
+pub struct DevnetEndpoints {
+    /// In-cluster JSON-RPC URL, including its `/rpc` path.
+    pub rpc: String,
+    /// In-cluster gateway URL.
+    pub gateway: String,
+    /// External JSON-RPC URL, if the devnet is reachable from outside the cluster.
+    pub external_rpc: Option<String>,
+    /// External gateway URL, if the devnet is reachable from outside the cluster.
+    pub external_gateway: Option<String>,
 }
 
-impl Default for DevnetState {
-    fn default() -> Self {
-        DevnetState::Created
+impl DevnetSpec {
+    /// Returns the `starknet-devnet` arguments for the spec, loading the state from
+    /// `load_path` if set.
+    ///
+    /// The arguments don't include the listen address, so the same spec runs in a pod or
+    /// as a local process.
+    pub fn args(&self, load_path: Option<&str>) -> Vec<String> {
+        let mut args = Vec::default();
+
+        if self.lite_mode.unwrap_or(false) {
+            args.push("--lite-mode".to_string());
+        }
+
+        if self.lite_mode_block_hash.unwrap_or(false) {
+            args.push("--lite-mode-block-hash".to_string());
+        }
+
+        if self.lite_mode_deploy_hash.unwrap_or(false) {
+            args.push("--lite-mode-deploy-hash".to_string());
+        }
+
+        if let Some(accounts) = self.accounts {
+            args.push(format!("--accounts={}", accounts));
+        }
+
+        if let Some(initial_balance) = &self.initial_balance {
+            args.push(format!("--initial-balance={}", initial_balance));
+        }
+
+        if let Some(seed) = &self.seed {
+            args.push(format!("--seed={}", seed));
+        }
+
+        if let Some(start_time) = self.start_time {
+            args.push(format!("--start-time={}", start_time));
+        }
+
+        if let Some(gas_price) = &self.gas_price {
+            args.push(format!("--gas-price={}", gas_price));
+        }
+
+        if let Some(block_production) = &self.block_production {
+            match block_production.mode.unwrap_or_default() {
+                BlockProductionMode::Transaction => {}
+                BlockProductionMode::Demand => args.push("--blocks-on-demand".to_string()),
+                BlockProductionMode::Interval => args.push(format!(
+                    "--block-generation-on={}",
+                    block_production
+                        .interval_seconds
+                        .unwrap_or(DEFAULT_BLOCK_INTERVAL_SECONDS)
+                )),
+            }
+        }
+
+        if let Some(dump_path) = load_path {
+            args.push(format!("--load-path={}", dump_path));
+        }
+
+        if let Some(extra_args) = &self.extra_args {
+            args.extend(extra_args.clone());
+        }
+
+        args
     }
 }
 
@@ -61,6 +372,139 @@ impl Devnet {
     pub fn state(&self) -> DevnetState {
         self.status().map(|s| s.state).unwrap_or_default()
     }
+
+    /// Returns true if the devnet opted out of expiry with the `ryogoku.stark/keep` annotation.
+    pub fn keep(&self) -> bool {
+        self.annotations()
+            .get(KEEP_ANNOTATION)
+            .map(|value| value != "false")
+            .unwrap_or(false)
+    }
+
+    /// Returns the time the devnet expires, given the last time a new block was seen.
+    pub fn expiry(&self, last_activity: Option<&Time>) -> Option<Time> {
+        if self.keep() {
+            return None;
+        }
+
+        let after_creation = self
+            .spec
+            .ttl_seconds_after_creation
+            .zip(self.metadata.creation_timestamp.as_ref())
+            .and_then(|(ttl, created)| expires_after(created, ttl));
+        let after_idle = self
+            .spec
+            .ttl_seconds_after_idle
+            .zip(last_activity.or(self.metadata.creation_timestamp.as_ref()))
+            .and_then(|(ttl, active)| expires_after(active, ttl));
+
+        match (after_creation, after_idle) {
+            (Some(a), Some(b)) => Some(Time(a.min(b))),
+            (a, b) => a.or(b).map(Time),
+        }
+    }
+
+    /// Returns the value of the restart annotation if the devnet was not restarted for it yet.
+    pub fn restart_requested(&self) -> Option<&str> {
+        let requested = self.annotations().get(RESTART_ANNOTATION)?;
+        let restarted_at = self.status().and_then(|s| s.restarted_at.as_ref());
+        (restarted_at != Some(requested)).then_some(requested.as_str())
+    }
+
+    /// Returns true if the devnet should be paused.
+    pub fn paused(&self) -> bool {
+        self.spec.paused.unwrap_or(false)
+    }
+
+    /// Returns true if the devnet has the `Ready` condition.
+    pub fn is_ready(&self) -> bool {
+        self.status()
+            .and_then(|s| s.condition("Ready"))
+            .map(|c| c.is_true())
+            .unwrap_or(false)
+    }
+}
+
+/// Returns the time `ttl_seconds` after `time`, or `None` if it's out of range and the devnet
+/// never expires.
+fn expires_after(time: &Time, ttl_seconds: u64) -> Option<DateTime<Utc>> {
+    let ttl = i64::try_from(ttl_seconds)
+        .ok()
+        .filter(|ttl| *ttl <= Duration::max_value().num_seconds())?;
+    time.0.checked_add_signed(Duration::seconds(ttl))
+}
+
+impl DevnetStatus {
+    /// Returns the condition with the given type.
+    pub fn condition(&self, type_: &str) -> Option<&DevnetCondition> {
+        self.conditions
+            .as_ref()
+            .and_then(|conditions| conditions.iter().find(|c| c.type_ == type_))
+    }
+
+    /// Sets a condition, keeping its last transition time if the status did not change.
+    pub fn set_condition(&mut self, mut condition: DevnetCondition) {
+        let conditions = self.conditions.get_or_insert_with(Vec::new);
+        match conditions.iter_mut().find(|c| c.type_ == condition.type_) {
+            Some(existing) => {
+                if existing.status == condition.status {
+                    condition.last_transition_time = existing.last_transition_time.clone();
+                }
+                *existing = condition;
+            }
+            None => conditions.push(condition),
+        }
+    }
+}
+
+impl DevnetCondition {
+    /// Creates a new condition, transitioning now.
+    pub fn new(type_: &str, status: bool, reason: &str, message: Option<String>) -> Self {
+        DevnetCondition {
+            type_: type_.to_string(),
+            status: if status { "True" } else { "False" }.to_string(),
+            reason: Some(reason.to_string()),
+            message,
+            last_transition_time: Some(Time(Utc::now())),
+        }
+    }
+
+    /// Returns true if the condition status is `True`.
+    pub fn is_true(&self) -> bool {
+        self.status == "True"
+    }
+}
+
+impl DevnetEndpoints {
+    /// Returns the rpc URL to use from outside the cluster, falling back to the in-cluster one.
+    pub fn preferred_rpc(&self) -> &str {
+        self.external_rpc.as_deref().unwrap_or(&self.rpc)
+    }
+
+    /// Returns the gateway URL to use from outside the cluster, falling back to the in-cluster one.
+    pub fn preferred_gateway(&self) -> &str {
+        self.external_gateway.as_deref().unwrap_or(&self.gateway)
+    }
+}
+
+impl DevnetExposure {
+    /// Returns the exposure mode.
+    pub fn mode(&self) -> ExposureMode {
+        self.mode.unwrap_or(ExposureMode::Ingress)
+    }
+
+    /// Returns the host, with the template variables replaced.
+    pub fn host_for(&self, name: &str, namespace: &str) -> String {
+        self.host
+            .replace("{name}", name)
+            .replace("{namespace}", namespace)
+    }
+
+    /// Returns the public URL of the devnet.
+    pub fn url_for(&self, name: &str, namespace: &str) -> String {
+        let scheme = if self.tls.is_some() { "https" } else { "http" };
+        format!("{}://{}", scheme, self.host_for(name, namespace))
+    }
 }
 
 impl Display for DevnetState {
@@ -69,6 +513,187 @@ impl Display for DevnetState {
             DevnetState::Created => write!(f, "CREATED"),
             DevnetState::Running => write!(f, "RUNNING"),
             DevnetState::Errored => write!(f, "ERRORED"),
+            DevnetState::Paused => write!(f, "PAUSED"),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn devnet(ttl_after_creation: Option<u64>, ttl_after_idle: Option<u64>) -> Devnet {
+        let mut devnet = Devnet::new(
+            "my-devnet",
+            DevnetSpec {
+                ttl_seconds_after_creation: ttl_after_creation,
+                ttl_seconds_after_idle: ttl_after_idle,
+                ..DevnetSpec::default()
+            },
+        );
+        devnet.metadata.creation_timestamp = Some(Time(Utc::now() - Duration::hours(1)));
+        devnet
+    }
+
+    #[test]
+    fn expiry_uses_the_earliest_ttl() {
+        let created = Utc::now() - Duration::hours(1);
+        let mut devnet = devnet(Some(7200), Some(600));
+        devnet.metadata.creation_timestamp = Some(Time(created));
+
+        let expiry = devnet.expiry(None).unwrap();
+        assert_eq!(expiry.0, created + Duration::seconds(600));
+
+        let active = Time(created + Duration::hours(2));
+        let expiry = devnet.expiry(Some(&active)).unwrap();
+        assert_eq!(expiry.0, created + Duration::seconds(7200));
+
+        let active = Time(created + Duration::minutes(30));
+        let expiry = devnet.expiry(Some(&active)).unwrap();
+        assert_eq!(expiry.0, active.0 + Duration::seconds(600));
+    }
+
+    #[test]
+    fn expiry_is_none_without_ttl_or_with_keep() {
+        assert_eq!(devnet(None, None).expiry(None), None);
+
+        let mut kept = devnet(Some(60), Some(60));
+        kept.metadata.annotations = Some(BTreeMap::from([(
+            KEEP_ANNOTATION.to_string(),
+            "true".to_string(),
+        )]));
+        assert_eq!(kept.expiry(None), None);
+    }
+
+    #[test]
+    fn expiry_out_of_range_never_expires() {
+        assert_eq!(devnet(Some(u64::MAX), None).expiry(None), None);
+        assert_eq!(devnet(None, Some(i64::MAX as u64)).expiry(None), None);
+
+        let expiry = devnet(Some(u64::MAX), Some(60)).expiry(None).unwrap();
+        assert!(expiry.0 < Utc::now());
+    }
+
+    #[test]
+    fn args_are_empty_by_default() {
+        assert!(DevnetSpec::default().args(None).is_empty());
+    }
+
+    #[test]
+    fn args_follow_the_spec() {
+        let spec = DevnetSpec {
+            lite_mode: Some(true),
+            lite_mode_block_hash: Some(false),
+            accounts: Some(3),
+            seed: Some("42".to_string()),
+            block_production: Some(BlockProduction {
+                mode: Some(BlockProductionMode::Interval),
+                ..BlockProduction::default()
+            }),
+            extra_args: Some(vec!["--timeout".to_string(), "120".to_string()]),
+            ..DevnetSpec::default()
+        };
+
+        assert_eq!(
+            spec.args(Some("/data/devnet.dump")),
+            [
+                "--lite-mode",
+                "--accounts=3",
+                "--seed=42",
+                "--block-generation-on=60",
+                "--load-path=/data/devnet.dump",
+                "--timeout",
+                "120",
+            ]
+        );
+    }
+
+    #[test]
+    fn args_block_production_modes() {
+        let args = |mode, interval_seconds| {
+            DevnetSpec {
+                block_production: Some(BlockProduction {
+                    mode,
+                    interval_seconds,
+                    time: Some(1_700_000_000),
+                }),
+                ..DevnetSpec::default()
+            }
+            .args(None)
+        };
+
+        assert!(args(None, None).is_empty());
+        assert!(args(Some(BlockProductionMode::Transaction), Some(5)).is_empty());
+        assert_eq!(
+            args(Some(BlockProductionMode::Demand), None),
+            ["--blocks-on-demand"]
+        );
+        assert_eq!(
+            args(Some(BlockProductionMode::Interval), Some(5)),
+            ["--block-generation-on=5"]
+        );
+    }
+
+    #[test]
+    fn set_condition_adds_and_replaces_conditions() {
+        let mut status = DevnetStatus::default();
+        status.set_condition(DevnetCondition::new("Ready", false, "Starting", None));
+        status.set_condition(DevnetCondition::new(
+            "RpcReachable",
+            true,
+            "RpcReachable",
+            None,
+        ));
+        status.set_condition(DevnetCondition::new("Ready", true, "PodReady", None));
+
+        let conditions = status.conditions.as_ref().unwrap();
+        assert_eq!(conditions.len(), 2);
+        let ready = status.condition("Ready").unwrap();
+        assert!(ready.is_true());
+        assert_eq!(ready.reason.as_deref(), Some("PodReady"));
+    }
+
+    #[test]
+    fn set_condition_keeps_transition_time_without_status_change() {
+        let mut status = DevnetStatus::default();
+        let mut first = DevnetCondition::new("Ready", false, "Starting", None);
+        first.last_transition_time = Some(Time(Utc::now() - Duration::hours(1)));
+        let transition = first.last_transition_time.clone();
+        status.set_condition(first);
+
+        status.set_condition(DevnetCondition::new("Ready", false, "Bootstrapping", None));
+        let ready = status.condition("Ready").unwrap();
+        assert_eq!(ready.reason.as_deref(), Some("Bootstrapping"));
+        assert_eq!(ready.last_transition_time, transition);
+
+        status.set_condition(DevnetCondition::new("Ready", true, "PodReady", None));
+        assert_ne!(
+            status.condition("Ready").unwrap().last_transition_time,
+            transition
+        );
+    }
+
+    #[test]
+    fn restart_is_requested_until_the_annotation_is_handled() {
+        let mut devnet = devnet(None, None);
+        assert_eq!(devnet.restart_requested(), None);
+
+        devnet.metadata.annotations = Some(BTreeMap::from([(
+            RESTART_ANNOTATION.to_string(),
+            "2024-01-01T00:00:00Z".to_string(),
+        )]));
+        assert_eq!(devnet.restart_requested(), Some("2024-01-01T00:00:00Z"));
+
+        devnet.status = Some(DevnetStatus {
+            restarted_at: Some("2024-01-01T00:00:00Z".to_string()),
+            ..DevnetStatus::default()
+        });
+        assert_eq!(devnet.restart_requested(), None);
+
+        devnet.metadata.annotations = Some(BTreeMap::from([(
+            RESTART_ANNOTATION.to_string(),
+            "2024-01-02T00:00:00Z".to_string(),
+        )]));
+        assert_eq!(devnet.restart_requested(), Some("2024-01-02T00:00:00Z"));
+    }
+}
@@ -0,0 +1,37 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Name of the bootstrap step migrating the Dojo world.
+pub(crate) static DOJO_STEP: &str = "dojo-migrate";
+
+/// Dojo world migrated onto the devnet.
+///
+/// The world is migrated with `sozo` as the first bootstrap step, and the optional Torii
+/// indexer starts once the world is deployed.
+#[derive(Deserialize, Serialize, Clone, Debug, JsonSchema, Default)]
+pub struct DevnetDojo {
+    /// Image holding the built Dojo project in `/project`. The image must provide `cp`.
+    pub project_image: String,
+    /// Image providing `sozo` and `torii`. Defaults to `ghcr.io/dojoengine/dojo:latest`.
+    pub image: Option<String>,
+    /// Run a Torii indexer for the world.
+    pub torii: Option<DojoTorii>,
+}
+
+/// Torii indexer configuration.
+#[derive(Deserialize, Serialize, Clone, Debug, JsonSchema, Default)]
+pub struct DojoTorii {
+    /// Image providing `torii`. Defaults to the Dojo image.
+    pub image: Option<String>,
+    /// Extra arguments for torii.
+    pub extra_args: Option<Vec<String>>,
+}
+
+/// Dojo world status.
+#[derive(Deserialize, Serialize, Clone, Default, Debug, JsonSchema, PartialEq)]
+pub struct DojoStatus {
+    /// Address of the migrated world.
+    pub world_address: Option<String>,
+    /// In-cluster Torii GraphQL URL.
+    pub torii_graphql: Option<String>,
+}
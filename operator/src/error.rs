@@ -2,6 +2,12 @@
 pub enum Error {
     #[error("ryogoku CRDs are not installed")]
     CrdNotInstalled,
+    #[error("Invalid devnet spec: {0}")]
+    InvalidSpec(String),
+    #[error("Devnet rpc error: {0}")]
+    Rpc(String),
+    #[error("Devnet exec error: {0}")]
+    Exec(String),
     #[error("Kube error: {0}")]
     Kube(#[from] kube::Error),
     #[error("Finalizer error: {0}")]
@@ -1,12 +1,53 @@
+mod bootstrap;
 pub mod controller;
 mod devnet;
+mod dojo;
 mod error;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+mod pool;
+mod rpc;
+mod template;
 
 pub use self::{
-    devnet::{Devnet, DevnetSpec, DevnetStatus},
+    bootstrap::{
+        BootstrapContracts, BootstrapDeclare, BootstrapDeploy, BootstrapPhase, BootstrapStatus,
+        BootstrapStep, BootstrapStepStatus,
+    },
+    devnet::{
+        BlockProduction, BlockProductionMode, Devnet, DevnetCloneSource, DevnetCondition,
+        DevnetEndpoints, DevnetExposure, DevnetHealth, DevnetL1, DevnetL1Status, DevnetSpec,
+        DevnetState, DevnetStatus, DevnetStorage, ExposureMode, ExposureTls, FundedAddress,
+        MintUnit, KEEP_ANNOTATION, RESTART_ANNOTATION,
+    },
+    dojo::{DevnetDojo, DojoStatus, DojoTorii},
     error::{Error, Result},
+    pool::{
+        DevnetClaim, DevnetClaimPhase, DevnetClaimSpec, DevnetClaimStatus, DevnetPool,
+        DevnetPoolSpec, DevnetPoolStatus, CLAIM_LABEL, POOL_LABEL,
+    },
+    rpc::{DevnetClient, MintResponse},
+    template::{
+        ClusterDevnetTemplate, ClusterDevnetTemplateSpec, DevnetTemplate, DevnetTemplateKind,
+        DevnetTemplateRef, DevnetTemplateSpec,
+    },
 };
 
+/// Returns the CRDs of all the resources managed by the operator.
+pub fn crds(
+) -> Vec<k8s_openapi::apiextensions_apiserver::pkg::apis::apiextensions::v1::CustomResourceDefinition>
+{
+    use kube::CustomResourceExt;
+
+    vec![
+        Devnet::crd(),
+        DevnetPool::crd(),
+        DevnetClaim::crd(),
+        DevnetTemplate::crd(),
+        ClusterDevnetTemplate::crd(),
+    ]
+}
+
 pub mod kube {
     pub use kube::*;
 }
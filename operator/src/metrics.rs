@@ -0,0 +1,159 @@
+//! Prometheus gauges of the devnet health, enabled with the `metrics` feature.
+
+use std::{convert::Infallible, net::SocketAddr, sync::OnceLock};
+
+use hyper::{
+    header::CONTENT_TYPE,
+    service::{make_service_fn, service_fn},
+    Body, Request, Response, Server, StatusCode,
+};
+use kube::ResourceExt;
+use prometheus::{register_int_gauge_vec, Encoder, IntGaugeVec, TextEncoder};
+
+use crate::devnet::{Devnet, DevnetHealth};
+
+struct Gauges {
+    block_number: IntGaugeVec,
+    rpc_reachable: IntGaugeVec,
+    last_seen: IntGaugeVec,
+    info: IntGaugeVec,
+}
+
+fn gauges() -> &'static Gauges {
+    static GAUGES: OnceLock<Gauges> = OnceLock::new();
+    GAUGES.get_or_init(|| Gauges {
+        block_number: register_int_gauge_vec!(
+            "ryogoku_devnet_block_number",
+            "Latest block number of the devnet.",
+            &["namespace", "devnet"]
+        )
+        .expect("valid gauge"),
+        rpc_reachable: register_int_gauge_vec!(
+            "ryogoku_devnet_rpc_reachable",
+            "1 if the devnet rpc endpoint answered the last health check.",
+            &["namespace", "devnet"]
+        )
+        .expect("valid gauge"),
+        last_seen: register_int_gauge_vec!(
+            "ryogoku_devnet_last_seen_timestamp_seconds",
+            "Last time the devnet rpc endpoint answered, in Unix time.",
+            &["namespace", "devnet"]
+        )
+        .expect("valid gauge"),
+        info: register_int_gauge_vec!(
+            "ryogoku_devnet_info",
+            "Chain id, rpc version and image tag of the devnet.",
+            &[
+                "namespace",
+                "devnet",
+                "chain_id",
+                "rpc_version",
+                "image_tag"
+            ]
+        )
+        .expect("valid gauge"),
+    })
+}
+
+/// Record the result of a devnet health check, replacing the `previous` one.
+pub(crate) fn record(devnet: &Devnet, previous: Option<&DevnetHealth>, health: &DevnetHealth) {
+    let ns = devnet.namespace().unwrap_or_default();
+    let name = devnet.name_any();
+    let labels = [ns.as_str(), name.as_str()];
+    let gauges = gauges();
+
+    let reachable =
+        health.last_seen_time.is_some() && health.last_seen_time == health.last_check_time;
+    gauges
+        .rpc_reachable
+        .with_label_values(&labels)
+        .set(reachable as i64);
+    if let Some(block_number) = health.block_number {
+        gauges
+            .block_number
+            .with_label_values(&labels)
+            .set(block_number as i64);
+    }
+    if let Some(last_seen) = &health.last_seen_time {
+        gauges
+            .last_seen
+            .with_label_values(&labels)
+            .set(last_seen.0.timestamp());
+    }
+
+    // the info labels change with the chain, drop the previous series.
+    if let Some(previous) = previous {
+        let _ = gauges
+            .info
+            .remove_label_values(&info_labels(&labels, previous));
+    }
+    gauges
+        .info
+        .with_label_values(&info_labels(&labels, health))
+        .set(1);
+}
+
+/// Record that the devnet rpc endpoint can't be reached, like while its pod is not ready.
+pub(crate) fn record_unreachable(devnet: &Devnet) {
+    let ns = devnet.namespace().unwrap_or_default();
+    let name = devnet.name_any();
+    gauges()
+        .rpc_reachable
+        .with_label_values(&[ns.as_str(), name.as_str()])
+        .set(0);
+}
+
+/// Remove the gauges of a deleted devnet.
+pub(crate) fn remove(devnet: &Devnet) {
+    let ns = devnet.namespace().unwrap_or_default();
+    let name = devnet.name_any();
+    let labels = [ns.as_str(), name.as_str()];
+    let gauges = gauges();
+
+    let _ = gauges.block_number.remove_label_values(&labels);
+    let _ = gauges.rpc_reachable.remove_label_values(&labels);
+    let _ = gauges.last_seen.remove_label_values(&labels);
+    if let Some(health) = devnet.status.as_ref().and_then(|s| s.health.as_ref()) {
+        let _ = gauges
+            .info
+            .remove_label_values(&info_labels(&labels, health));
+    }
+}
+
+fn info_labels<'a>(labels: &[&'a str; 2], health: &'a DevnetHealth) -> [&'a str; 5] {
+    [
+        labels[0],
+        labels[1],
+        health.chain_id.as_deref().unwrap_or_default(),
+        health.rpc_version.as_deref().unwrap_or_default(),
+        health.image_tag.as_deref().unwrap_or_default(),
+    ]
+}
+
+/// Serve the gauges in the Prometheus text format on `/metrics`.
+pub async fn serve(addr: SocketAddr) -> Result<(), hyper::Error> {
+    let make_service = make_service_fn(|_| async { Ok::<_, Infallible>(service_fn(handle)) });
+    Server::bind(&addr).serve(make_service).await
+}
+
+async fn handle(request: Request<Body>) -> Result<Response<Body>, Infallible> {
+    if request.uri().path() != "/metrics" {
+        let response = Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Body::empty())
+            .expect("valid response");
+        return Ok(response);
+    }
+
+    let encoder = TextEncoder::new();
+    let mut buffer = Vec::new();
+    let response = match encoder.encode(&prometheus::gather(), &mut buffer) {
+        Ok(()) => Response::builder()
+            .header(CONTENT_TYPE, encoder.format_type())
+            .body(Body::from(buffer)),
+        Err(err) => Response::builder()
+            .status(StatusCode::INTERNAL_SERVER_ERROR)
+            .body(Body::from(err.to_string())),
+    };
+    Ok(response.expect("valid response"))
+}
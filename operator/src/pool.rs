@@ -0,0 +1,77 @@
+use kube::CustomResource;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::devnet::DevnetSpec;
+
+/// Label set on the devnets of a pool, with the pool name.
+pub static POOL_LABEL: &str = "ryogoku.stark/pool";
+/// Label set on claimed devnets, with the claim name.
+pub static CLAIM_LABEL: &str = "ryogoku.stark/claim";
+
+/// Pool of pre-started development networks, ready to be claimed.
+#[derive(CustomResource, Deserialize, Serialize, Clone, Debug, JsonSchema, Default)]
+#[kube(
+    kind = "DevnetPool",
+    group = "ryogoku.stark",
+    version = "v1",
+    namespaced
+)]
+#[kube(status = "DevnetPoolStatus", shortname = "devnetpool")]
+pub struct DevnetPoolSpec {
+    /// Number of unclaimed devnets to keep in the pool.
+    pub replicas: usize,
+    /// Spec of the devnets created by the pool.
+    pub template: DevnetSpec,
+}
+
+/// Devnet pool status.
+#[derive(Deserialize, Serialize, Clone, Default, Debug, JsonSchema, PartialEq, Eq)]
+pub struct DevnetPoolStatus {
+    /// Number of unclaimed devnets.
+    pub replicas: usize,
+    /// Number of unclaimed devnets that are ready.
+    pub ready_replicas: usize,
+    /// Number of claimed devnets.
+    pub claimed: usize,
+}
+
+/// Claim binding a ready devnet from a pool. The devnet is deleted with the claim.
+#[derive(CustomResource, Deserialize, Serialize, Clone, Debug, JsonSchema, Default)]
+#[kube(
+    kind = "DevnetClaim",
+    group = "ryogoku.stark",
+    version = "v1",
+    namespaced
+)]
+#[kube(status = "DevnetClaimStatus", shortname = "devnetclaim")]
+pub struct DevnetClaimSpec {
+    /// Name of the pool to claim a devnet from, in the claim namespace.
+    pub pool: String,
+}
+
+/// Phase of the claim.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, JsonSchema, PartialEq, Eq, Default)]
+pub enum DevnetClaimPhase {
+    /// Waiting for a ready devnet.
+    #[default]
+    Pending,
+    /// A devnet is bound to the claim.
+    Bound,
+}
+
+/// Devnet claim status.
+#[derive(Deserialize, Serialize, Clone, Default, Debug, JsonSchema, PartialEq, Eq)]
+pub struct DevnetClaimStatus {
+    /// Claim phase.
+    pub phase: DevnetClaimPhase,
+    /// Name of the bound devnet.
+    pub devnet: Option<String>,
+}
+
+impl DevnetClaim {
+    /// Returns the name of the bound devnet, if any.
+    pub fn devnet(&self) -> Option<&str> {
+        self.status.as_ref().and_then(|s| s.devnet.as_deref())
+    }
+}
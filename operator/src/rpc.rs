@@ -0,0 +1,165 @@
+use http::Request;
+use kube::Client;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{devnet::MintUnit, error::Result, Error};
+
+/// Query parameter marking the requests of the operator in the devnet access log, so that they
+/// don't count as devnet activity.
+pub(crate) static OPERATOR_QUERY: &str = "source=ryogoku-operator";
+
+/// Client for the devnet http endpoints.
+///
+/// Requests go through the api server service proxy, so the client works both from inside
+/// the cluster and from the cli.
+#[derive(Clone)]
+pub struct DevnetClient {
+    client: Client,
+    namespace: String,
+    name: String,
+    query: Option<&'static str>,
+}
+
+/// Response of the `mint` endpoint.
+#[derive(Deserialize, Debug)]
+pub struct MintResponse {
+    /// Balance of the address after minting.
+    pub new_balance: Value,
+    /// Unit of the balance.
+    pub unit: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct JsonRpcResponse<T> {
+    result: Option<T>,
+    error: Option<Value>,
+}
+
+impl DevnetClient {
+    /// Creates a new client for the devnet with the given name.
+    pub fn new(client: Client, namespace: &str, name: &str) -> Self {
+        DevnetClient {
+            client,
+            namespace: namespace.to_string(),
+            name: name.to_string(),
+            query: None,
+        }
+    }
+
+    /// Creates a new client for the operator, whose requests are marked with `OPERATOR_QUERY`.
+    pub(crate) fn for_operator(client: Client, namespace: &str, name: &str) -> Self {
+        DevnetClient {
+            query: Some(OPERATOR_QUERY),
+            ..DevnetClient::new(client, namespace, name)
+        }
+    }
+
+    fn proxy_url(&self, port: &str, path: &str) -> String {
+        let url = format!(
+            "/api/v1/namespaces/{}/services/{}:{}/proxy/{}",
+            self.namespace,
+            self.name,
+            port,
+            path.trim_start_matches('/')
+        );
+        match self.query {
+            Some(query) => format!("{}?{}", url, query),
+            None => url,
+        }
+    }
+
+    /// Call a JSON-RPC method on the `rpc` port.
+    pub async fn rpc<T: DeserializeOwned>(&self, method: &str, params: Value) -> Result<T> {
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": params,
+        });
+        let response: JsonRpcResponse<T> = self.post("rpc", "rpc", &body).await?;
+
+        if let Some(error) = response.error {
+            return Err(Error::Rpc(format!("{} failed: {}", method, error)));
+        }
+        response
+            .result
+            .ok_or_else(|| Error::Rpc(format!("{} returned no result", method)))
+    }
+
+    /// Send a GET request to the path on the given service port.
+    pub async fn get<T: DeserializeOwned>(&self, port: &str, path: &str) -> Result<T> {
+        let request = Request::get(self.proxy_url(port, path))
+            .body(Vec::new())
+            .map_err(|err| Error::Rpc(err.to_string()))?;
+        self.send(request).await
+    }
+
+    /// Send a POST request with a JSON body to the path on the given service port.
+    pub async fn post<T: DeserializeOwned>(
+        &self,
+        port: &str,
+        path: &str,
+        body: &impl Serialize,
+    ) -> Result<T> {
+        let request = Request::post(self.proxy_url(port, path))
+            .header("Content-Type", "application/json")
+            .body(serde_json::to_vec(body).map_err(|err| Error::Rpc(err.to_string()))?)
+            .map_err(|err| Error::Rpc(err.to_string()))?;
+        self.send(request).await
+    }
+
+    /// Send the request, parsing an empty response body as `null`.
+    async fn send<T: DeserializeOwned>(&self, request: Request<Vec<u8>>) -> Result<T> {
+        let text = self.client.request_text(request).await?;
+        let text = if text.trim().is_empty() {
+            "null"
+        } else {
+            &text
+        };
+        serde_json::from_str(text).map_err(|err| Error::Rpc(format!("invalid response: {}", err)))
+    }
+
+    /// Mint tokens to the address.
+    pub async fn mint(
+        &self,
+        address: &str,
+        amount: u128,
+        unit: Option<MintUnit>,
+    ) -> Result<MintResponse> {
+        // amounts don't fit in a json value, serialize them directly.
+        #[derive(Serialize)]
+        struct Mint<'a> {
+            address: &'a str,
+            amount: u128,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            unit: Option<&'static str>,
+        }
+
+        let body = Mint {
+            address,
+            amount,
+            unit: unit.map(|unit| match unit {
+                MintUnit::Wei => "WEI",
+                MintUnit::Fri => "FRI",
+            }),
+        };
+        self.post("gateway", "mint", &body).await
+    }
+
+    /// Returns the latest block number.
+    pub async fn block_number(&self) -> Result<u64> {
+        self.rpc("starknet_blockNumber", json!([])).await
+    }
+
+    /// Returns the chain id, as a hex encoded string.
+    pub async fn chain_id(&self) -> Result<String> {
+        self.rpc("starknet_chainId", json!([])).await
+    }
+
+    /// Returns the version of the JSON-RPC specification served by the devnet.
+    pub async fn spec_version(&self) -> Result<String> {
+        self.rpc("starknet_specVersion", json!([])).await
+    }
+}
@@ -0,0 +1,144 @@
+use kube::CustomResource;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::devnet::DevnetSpec;
+
+/// Reusable devnet preset, referenced by devnets in the same namespace.
+#[derive(CustomResource, Deserialize, Serialize, Clone, Debug, JsonSchema, Default)]
+#[kube(
+    kind = "DevnetTemplate",
+    group = "ryogoku.stark",
+    version = "v1",
+    namespaced
+)]
+#[kube(shortname = "devnettemplate")]
+pub struct DevnetTemplateSpec {
+    /// Devnet spec fields, overridden by the devnet own fields.
+    pub template: DevnetSpec,
+}
+
+/// Reusable devnet preset, referenced by devnets in any namespace.
+#[derive(CustomResource, Deserialize, Serialize, Clone, Debug, JsonSchema, Default)]
+#[kube(
+    kind = "ClusterDevnetTemplate",
+    group = "ryogoku.stark",
+    version = "v1"
+)]
+#[kube(shortname = "clusterdevnettemplate")]
+pub struct ClusterDevnetTemplateSpec {
+    /// Devnet spec fields, overridden by the devnet own fields.
+    pub template: DevnetSpec,
+}
+
+/// Reference to the template of a devnet.
+#[derive(Deserialize, Serialize, Clone, Debug, JsonSchema, Default)]
+pub struct DevnetTemplateRef {
+    /// Template name.
+    pub name: String,
+    /// Template kind. Defaults to `DevnetTemplate`.
+    pub kind: Option<DevnetTemplateKind>,
+}
+
+/// Kind of devnet template.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, JsonSchema, PartialEq, Eq, Default)]
+pub enum DevnetTemplateKind {
+    /// Template in the devnet namespace.
+    #[default]
+    DevnetTemplate,
+    /// Cluster-scoped template.
+    ClusterDevnetTemplate,
+}
+
+impl DevnetSpec {
+    /// Returns the spec with its fields merged over the template fields.
+    ///
+    /// Objects are merged recursively, other values set in the spec replace the template ones.
+    pub fn merged_over(&self, template: &DevnetSpec) -> DevnetSpec {
+        let mut merged = serde_json::to_value(template).expect("devnet spec serializes");
+        merge(
+            &mut merged,
+            serde_json::to_value(self).expect("devnet spec serializes"),
+        );
+        serde_json::from_value(merged).expect("merged devnet spec deserializes")
+    }
+}
+
+fn merge(base: &mut Value, overrides: Value) {
+    match (base, overrides) {
+        (_, Value::Null) => {}
+        (Value::Object(base), Value::Object(overrides)) => {
+            for (key, value) in overrides {
+                merge(base.entry(key).or_insert(Value::Null), value);
+            }
+        }
+        (base, overrides) => *base = overrides,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::devnet::{BlockProduction, BlockProductionMode};
+
+    fn template() -> DevnetSpec {
+        DevnetSpec {
+            image: Some("shardlabs/starknet-devnet:0.5.0".to_string()),
+            accounts: Some(10),
+            extra_args: Some(vec!["--timeout".to_string(), "60".to_string()]),
+            block_production: Some(BlockProduction {
+                mode: Some(BlockProductionMode::Interval),
+                interval_seconds: Some(30),
+                time: None,
+            }),
+            ..DevnetSpec::default()
+        }
+    }
+
+    #[test]
+    fn merged_over_keeps_template_fields_not_set_in_the_spec() {
+        let spec = DevnetSpec {
+            seed: Some("42".to_string()),
+            ..DevnetSpec::default()
+        };
+
+        let merged = spec.merged_over(&template());
+        assert_eq!(
+            merged.image.as_deref(),
+            Some("shardlabs/starknet-devnet:0.5.0")
+        );
+        assert_eq!(merged.accounts, Some(10));
+        assert_eq!(merged.seed.as_deref(), Some("42"));
+    }
+
+    #[test]
+    fn merged_over_replaces_values_and_lists() {
+        let spec = DevnetSpec {
+            accounts: Some(2),
+            extra_args: Some(vec!["--lite-mode".to_string()]),
+            ..DevnetSpec::default()
+        };
+
+        let merged = spec.merged_over(&template());
+        assert_eq!(merged.accounts, Some(2));
+        assert_eq!(merged.extra_args, Some(vec!["--lite-mode".to_string()]));
+    }
+
+    #[test]
+    fn merged_over_merges_objects_recursively() {
+        let spec = DevnetSpec {
+            block_production: Some(BlockProduction {
+                interval_seconds: Some(5),
+                ..BlockProduction::default()
+            }),
+            ..DevnetSpec::default()
+        };
+
+        let merged = spec.merged_over(&template());
+        let block_production = merged.block_production.unwrap();
+        assert_eq!(block_production.mode, Some(BlockProductionMode::Interval));
+        assert_eq!(block_production.interval_seconds, Some(5));
+        assert_eq!(block_production.time, None);
+    }
+}